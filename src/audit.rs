@@ -0,0 +1,119 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::identity;
+use super::Rule;
+
+// Appends one line per decision taken on a protected path, for example:
+//   time=1623283200 pid=4242 uid=0 user="root" invoking_uid=1000
+//   invoking_user="alice" event="skipped" path="/srv" reason="..." owner="..."
+// followed by the exit status of the real rm once it has finished.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    // Whether any decision was recorded during this run.
+    recorded: Cell<bool>,
+}
+
+impl AuditLog {
+    pub fn new(path: Option<&str>) -> AuditLog {
+        AuditLog {
+            path: path.filter(|p| !p.is_empty()).map(PathBuf::from),
+            recorded: Cell::new(false),
+        }
+    }
+
+    pub fn record(&self, event: &str, arg: &OsStr, rule: &Rule) {
+        self.write(&format_record(event, arg, rule));
+        self.recorded.set(self.path.is_some());
+    }
+
+    // Whether the exit status of the real rm needs to be recorded.
+    pub fn awaits_status(&self) -> bool {
+        self.recorded.get()
+    }
+
+    pub fn record_status(&self, status: &str) {
+        self.write(&format!(
+            "{} event=\"finished\" status={:?}",
+            record_prefix(),
+            status
+        ));
+    }
+
+    fn write(&self, record: &str) {
+        if let Some(path) = &self.path {
+            if let Err(e) = append(path, record) {
+                println!(
+                    "safe-rm: Could not write to audit log {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+// Who did what and when.
+fn record_prefix() -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut record = format!(
+        "time={} pid={} uid={}",
+        time,
+        std::process::id(),
+        identity::effective_uid()
+    );
+    if let Some(user) = identity::current_user() {
+        record.push_str(&format!(" user={:?}", user.name));
+    }
+    if let Some(user) = identity::invoking_user() {
+        record.push_str(&format!(
+            " invoking_uid={} invoking_user={:?}",
+            user.uid, user.name
+        ));
+    }
+    record
+}
+
+pub fn format_record(event: &str, arg: &OsStr, rule: &Rule) -> String {
+    let mut record = record_prefix();
+    record.push_str(&format!(
+        " event={:?} path={:?}",
+        event,
+        arg.to_string_lossy()
+    ));
+    if let Some(reason) = &rule.reason {
+        record.push_str(&format!(" reason={:?}", reason));
+    }
+    if let Some(owner) = &rule.owner {
+        record.push_str(&format!(" owner={:?}", owner));
+    }
+    record
+}
+
+fn append(path: &Path, record: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record)
+}
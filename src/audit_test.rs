@@ -0,0 +1,99 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn format_record() {
+        use super::super::audit::format_record;
+        use super::super::identity::effective_uid;
+        use super::super::Rule;
+
+        let mut rule = Rule::new(PathBuf::from("/srv"));
+        let record = format_record("skipped", OsStr::new("/srv"), &rule);
+        assert!(record.starts_with("time="));
+        assert!(record.contains(&format!(" uid={} ", effective_uid())));
+        assert!(record.ends_with(" event=\"skipped\" path=\"/srv\""));
+
+        rule.reason = Some("production \"data\"".to_string());
+        rule.owner = Some("#db-oncall".to_string());
+        let record = format_record("warned", OsStr::new("/srv"), &rule);
+        assert!(record.ends_with(
+            " event=\"warned\" path=\"/srv\" reason=\"production \\\"data\\\"\" owner=\"#db-oncall\""
+        ));
+    }
+
+    #[test]
+    fn record() {
+        use super::super::audit::AuditLog;
+        use super::super::Rule;
+
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("audit.log");
+        let rule = Rule::new(PathBuf::from("/srv"));
+
+        // Disabled logs don't write anything.
+        AuditLog::new(None).record("skipped", OsStr::new("/srv"), &rule);
+        AuditLog::new(Some("")).record("skipped", OsStr::new("/srv"), &rule);
+        assert!(!log_path.exists());
+
+        let audit_log = AuditLog::new(log_path.to_str());
+        audit_log.record("skipped", OsStr::new("/srv"), &rule);
+        audit_log.record("warned", OsStr::new("/srv"), &rule);
+        let contents = fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("event=\"skipped\""));
+        assert!(lines[1].contains("event=\"warned\""));
+    }
+
+    #[test]
+    fn record_status() {
+        use super::super::audit::AuditLog;
+        use super::super::Rule;
+
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("audit.log");
+
+        let audit_log = AuditLog::new(None);
+        audit_log.record(
+            "skipped",
+            OsStr::new("/srv"),
+            &Rule::new(PathBuf::from("/srv")),
+        );
+        assert!(!audit_log.awaits_status());
+
+        let audit_log = AuditLog::new(log_path.to_str());
+        assert!(!audit_log.awaits_status());
+        audit_log.record(
+            "warned",
+            OsStr::new("/srv"),
+            &Rule::new(PathBuf::from("/srv")),
+        );
+        assert!(audit_log.awaits_status());
+        audit_log.record_status("130");
+        let contents = fs::read_to_string(&log_path).unwrap();
+        assert!(contents
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(" event=\"finished\" status=\"130\""));
+    }
+}
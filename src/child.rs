@@ -0,0 +1,70 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Running the real rm. safe-rm replaces itself with it whenever it has
+// nothing left to do afterwards, so that the shell sees rm's exit status,
+// including deaths by signal. Otherwise it forwards the signals used to
+// interrupt a command and then dies the same way rm did.
+
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::convert::TryFrom;
+use std::io;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command, ExitStatus};
+
+const FORWARDED_SIGNALS: &[i32] = &[SIGHUP, SIGINT, SIGTERM];
+
+// Only returns if the command couldn't be executed.
+pub fn exec(command: &mut Command) -> io::Error {
+    command.exec()
+}
+
+pub fn run(command: &mut Command) -> io::Result<ExitStatus> {
+    let mut signals = Signals::new(FORWARDED_SIGNALS)?;
+    let mut child = command.spawn()?;
+    let pid = Pid::from_raw(child.id() as i32);
+    let handle = signals.handle();
+    let forwarder = std::thread::spawn(move || {
+        for signal in signals.forever() {
+            if let Ok(signal) = Signal::try_from(signal) {
+                // The child may have exited in the meantime.
+                let _ = signal::kill(pid, signal);
+            }
+        }
+    });
+    let status = child.wait();
+    handle.close();
+    let _ = forwarder.join();
+    status
+}
+
+// The shell convention for processes killed by a signal is 128 + signal.
+pub fn exit_code(status: ExitStatus) -> i32 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code,
+        (None, Some(signal)) => 128 + signal,
+        (None, None) => 1,
+    }
+}
+
+// Dies from the same signal as the child, if it was killed by one.
+pub fn reraise(status: ExitStatus) {
+    if let Some(signal) = status.signal() {
+        let _ = signal_hook::low_level::emulate_default_handler(signal);
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    #[test]
+    fn run() {
+        use super::super::child::{exit_code, run};
+
+        let status = run(Command::new("sh").args(["-c", "exit 3"])).unwrap();
+        assert_eq!(exit_code(status), 3);
+        let status = run(&mut Command::new("true")).unwrap();
+        assert_eq!(exit_code(status), 0);
+
+        // Killed by a signal
+        let status = run(Command::new("sh").args(["-c", "kill -TERM $$"])).unwrap();
+        assert_eq!(exit_code(status), 128 + 15);
+        let status = run(Command::new("sh").args(["-c", "kill -KILL $$"])).unwrap();
+        assert_eq!(exit_code(status), 128 + 9);
+
+        assert!(run(&mut Command::new("/nonexistent/rm")).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Helper commands, only available when the binary is invoked as "safe-rm"
+// rather than "rm" so that they cannot clash with the names of files:
+//   safe-rm protect --xattr|--session PATH...
+//   safe-rm unprotect --xattr|--session PATH...
+//   safe-rm unlock PATH... --for DURATION --reason REASON [--once] [--user USER]
+// as well as "safe-rm check-config", which main handles since it needs the
+// configuration.
+
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::unlock::{self, Token};
+use super::{identity, normalize_path, predicates, session, xattrs};
+
+const PROGRAM_NAME: &str = "safe-rm";
+
+pub fn invoked_as_safe_rm(program: &OsStr) -> bool {
+    Path::new(program).file_name() == Some(OsStr::new(PROGRAM_NAME))
+}
+
+// Returns the exit code of the helper command, or None if the arguments
+// aren't a helper command and should be passed on to rm.
+pub fn run(args: &[OsString]) -> Option<i32> {
+    let command = args.first()?.to_str()?;
+    let args = &args[1..];
+    match command {
+        "protect" => Some(protect(args)),
+        "unprotect" => Some(unprotect(args)),
+        "unlock" => Some(unlock_paths(args)),
+        _ => None,
+    }
+}
+
+fn split_option<'a>(args: &'a [OsString], option: &str) -> Option<&'a [OsString]> {
+    match args.first() {
+        Some(first) if first == option && args.len() > 1 => Some(&args[1..]),
+        _ => None,
+    }
+}
+
+// Where a path is protected: in an extended attribute or for the session.
+fn protection_target<'a>(
+    args: &'a [OsString],
+    usage: &str,
+) -> Option<(&'static str, &'a [OsString])> {
+    for option in &["--xattr", "--session"] {
+        if let Some(paths) = split_option(args, option) {
+            return Some((option, paths));
+        }
+    }
+    println!("safe-rm: Usage: {}", usage);
+    None
+}
+
+fn for_each_path(paths: &[OsString], verb: &str, apply: impl Fn(&Path) -> io::Result<()>) -> i32 {
+    let mut status = 0;
+    for path in paths {
+        if let Err(e) = apply(Path::new(path)) {
+            println!(
+                "safe-rm: Could not {} {}: {}",
+                verb,
+                path.to_string_lossy(),
+                e
+            );
+            status = 1;
+        }
+    }
+    status
+}
+
+fn session_file() -> Option<PathBuf> {
+    let file = session::session_file(std::env::var_os(session::RUNTIME_DIR_VAR));
+    if file.is_none() {
+        println!(
+            "safe-rm: Cannot find the login session: {} is not set.",
+            session::RUNTIME_DIR_VAR
+        );
+    }
+    file
+}
+
+fn protect(args: &[OsString]) -> i32 {
+    match protection_target(args, "safe-rm protect --xattr|--session PATH...") {
+        Some(("--xattr", paths)) => {
+            for_each_path(paths, "protect", |path| xattrs::protect(path, ""))
+        }
+        Some((_, paths)) => match session_file() {
+            Some(file) => for_each_path(paths, "protect", |path| session::protect(&file, path)),
+            None => 1,
+        },
+        None => 1,
+    }
+}
+
+fn unprotect(args: &[OsString]) -> i32 {
+    match protection_target(args, "safe-rm unprotect --xattr|--session PATH...") {
+        Some(("--xattr", paths)) => for_each_path(paths, "unprotect", xattrs::unprotect),
+        Some((_, paths)) => match session_file() {
+            Some(file) => for_each_path(paths, "unprotect", |path| session::unprotect(&file, path)),
+            None => 1,
+        },
+        None => 1,
+    }
+}
+
+fn unlock_paths(args: &[OsString]) -> i32 {
+    let mut paths = Vec::new();
+    let (mut duration, mut reason, mut once) = (None, None, false);
+    let mut user = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--for") => {
+                duration = args
+                    .next()
+                    .and_then(|value| value.to_str())
+                    .and_then(predicates::parse_duration)
+            }
+            Some("--reason") => reason = args.next().map(|value| value.to_string_lossy()),
+            Some("--once") => once = true,
+            Some("--user") => user = args.next().map(|value| value.to_string_lossy()),
+            _ => paths.push(arg.clone()),
+        }
+    }
+    let (duration, reason) = match (duration, reason) {
+        (Some(duration), Some(reason)) if !paths.is_empty() && !reason.trim().is_empty() => {
+            (duration, reason)
+        }
+        _ => {
+            println!(
+                "safe-rm: Usage: safe-rm unlock PATH... --for DURATION --reason REASON [--once] [--user USER]"
+            );
+            return 1;
+        }
+    };
+
+    let effective_uid = identity::effective_uid();
+    let uid = match user {
+        None => effective_uid,
+        Some(_) if effective_uid != 0 => {
+            println!("safe-rm: Only root can unlock paths for other users.");
+            return 1;
+        }
+        // The user couldn't remove a single-use token from root's directory.
+        Some(_) if once => {
+            println!("safe-rm: --once cannot be used with --user.");
+            return 1;
+        }
+        Some(user) => match identity::lookup_user(&user) {
+            Some(identity) => identity.uid,
+            None => {
+                println!("safe-rm: Unknown user {}.", user);
+                return 1;
+            }
+        },
+    };
+    let dir = match unlock::unlock_dir(effective_uid, std::env::var_os(session::RUNTIME_DIR_VAR)) {
+        Some(dir) => dir,
+        None => {
+            println!(
+                "safe-rm: Cannot find the login session: {} is not set.",
+                session::RUNTIME_DIR_VAR
+            );
+            return 1;
+        }
+    };
+    let expires = unlock::now() + duration;
+    for_each_path(&paths, "unlock", |path| {
+        fs::symlink_metadata(path)?;
+        unlock::create(
+            &dir,
+            &Token {
+                path: PathBuf::from(normalize_path(path.as_os_str())),
+                uid,
+                expires,
+                reason: reason.to_string(),
+                once,
+            },
+        )
+    })
+}
@@ -0,0 +1,90 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::args;
+    use std::ffi::OsStr;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn invoked_as_safe_rm() {
+        use super::super::commands::invoked_as_safe_rm;
+
+        assert!(invoked_as_safe_rm(OsStr::new("safe-rm")));
+        assert!(invoked_as_safe_rm(OsStr::new("/usr/bin/safe-rm")));
+        assert!(!invoked_as_safe_rm(OsStr::new("rm")));
+        assert!(!invoked_as_safe_rm(OsStr::new("/usr/share/safe-rm/bin/rm")));
+        assert!(!invoked_as_safe_rm(OsStr::new("")));
+    }
+
+    #[test]
+    fn run() {
+        use super::super::commands::run;
+        use super::super::xattrs::xattr_rule;
+
+        // Not helper commands
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&args(&["-rf", "protect"])), None);
+
+        // Missing arguments
+        assert_eq!(run(&args(&["protect"])), Some(1));
+        assert_eq!(run(&args(&["protect", "--xattr"])), Some(1));
+        assert_eq!(run(&args(&["unprotect", "/tmp"])), Some(1));
+        assert_eq!(run(&args(&["protect", "--session"])), Some(1));
+        assert_eq!(run(&args(&["unlock", "/tmp", "--reason", "x"])), Some(1));
+        assert_eq!(run(&args(&["unlock", "/tmp", "--for", "10m"])), Some(1));
+        assert_eq!(
+            run(&args(&["unlock", "/tmp", "--for", "soon", "--reason", "x"])),
+            Some(1)
+        );
+        assert_eq!(
+            run(&args(&["unlock", "--for", "10m", "--reason", "x"])),
+            Some(1)
+        );
+        let unlock_for = |user: &str, once: &str| {
+            run(&args(&[
+                "unlock", "/tmp", "--for", "10m", "--reason", "x", "--user", user, once,
+            ]))
+        };
+        assert_eq!(unlock_for("no-such-user-for-safe-rm", "/tmp"), Some(1));
+        assert_eq!(unlock_for("root", "--once"), Some(1));
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("file");
+        File::create(&file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+        if xattr::set(&file_path, "user.safe-rm.test", b"").is_err() {
+            return; // The filesystem doesn't support extended attributes.
+        }
+
+        assert_eq!(run(&args(&["protect", "--xattr", file])), Some(0));
+        assert!(xattr_rule(&file_path).is_some());
+        assert_eq!(run(&args(&["unprotect", "--xattr", file])), Some(0));
+        assert!(xattr_rule(&file_path).is_none());
+
+        let missing = dir.path().join("missing");
+        assert_eq!(
+            run(&args(&[
+                "protect",
+                "--xattr",
+                file,
+                missing.to_str().unwrap()
+            ])),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,373 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Protection rules generated at runtime from the state of the system rather
+// than read from configuration files.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use super::{Action, Rule};
+
+pub const PROC_DIR: &str = "/proc";
+
+fn process_ids(proc_dir: &Path) -> Vec<u32> {
+    match fs::read_dir(proc_dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn command_name(proc_dir: &Path, pid: u32) -> String {
+    fs::read_to_string(proc_dir.join(pid.to_string()).join("comm"))
+        .map(|comm| comm.trim_end().to_string())
+        .unwrap_or_default()
+}
+
+// Files held open or mapped by a running process, along with every
+// directory containing them.
+pub fn open_file_rules(proc_dir: &Path, action: Action) -> Vec<Rule> {
+    let mut in_use: BTreeMap<PathBuf, (u32, String)> = BTreeMap::new();
+    for pid in process_ids(proc_dir) {
+        if pid == std::process::id() {
+            continue;
+        }
+        let process_dir = proc_dir.join(pid.to_string());
+        let command = command_name(proc_dir, pid);
+
+        // Other users' processes are usually unreadable and get skipped.
+        if let Ok(fds) = fs::read_dir(process_dir.join("fd")) {
+            for fd in fds.flatten() {
+                if let Some(path) = fs::read_link(fd.path()).ok().and_then(fd_target) {
+                    in_use.entry(path).or_insert_with(|| (pid, command.clone()));
+                }
+            }
+        }
+        if let Ok(maps) = fs::read_to_string(process_dir.join("maps")) {
+            for path in maps.lines().filter_map(mapped_path) {
+                in_use.entry(path).or_insert_with(|| (pid, command.clone()));
+            }
+        }
+    }
+
+    in_use
+        .into_iter()
+        .map(|(path, (pid, command))| Rule {
+            reason: Some(format!(
+                "{} is in use by PID {} ({})",
+                path.display(),
+                pid,
+                command
+            )),
+            action,
+            guard_ancestors: true,
+            ..Rule::new(path)
+        })
+        .collect()
+}
+
+// Only keep file descriptors pointing at files which still exist, as opposed
+// to sockets, pipes or deleted files.
+pub fn fd_target(target: PathBuf) -> Option<PathBuf> {
+    if !target.is_absolute() || target.to_string_lossy().ends_with(" (deleted)") {
+        return None;
+    }
+    Some(target)
+}
+
+// Extracts the pathname, if any, from a line of /proc/PID/maps:
+//   address perms offset dev inode pathname
+pub fn mapped_path(line: &str) -> Option<PathBuf> {
+    let mut rest = line;
+    for _ in 0..5 {
+        rest = rest.trim_start();
+        rest = &rest[rest.find(char::is_whitespace)?..];
+    }
+    let pathname = rest.trim();
+    if !pathname.starts_with('/') || pathname.ends_with(" (deleted)") {
+        return None;
+    }
+    Some(PathBuf::from(pathname))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Lock {
+    pub kind: String,
+    // OFD locks aren't owned by any particular process.
+    pub pid: Option<u32>,
+    pub major: u64,
+    pub minor: u64,
+    pub inode: u64,
+}
+
+// Parses a line of /proc/locks, for example:
+//   1: POSIX  ADVISORY  WRITE 1234 08:01:1310731 0 EOF
+// Lines for processes waiting on a lock ("1: -> POSIX ...") are ignored.
+pub fn parse_lock(line: &str) -> Option<Lock> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 6 || fields[1] == "->" {
+        return None;
+    }
+    let mut device = fields[5].split(':');
+    let major = u64::from_str_radix(device.next()?, 16).ok()?;
+    let minor = u64::from_str_radix(device.next()?, 16).ok()?;
+    let inode = device.next()?.parse().ok()?;
+    Some(Lock {
+        kind: fields[1].to_string(),
+        pid: fields[4].parse().ok(),
+        major,
+        minor,
+        inode,
+    })
+}
+
+// Splits a device number the same way as the kernel's MAJOR() and MINOR().
+pub fn device_numbers(dev: u64) -> (u64, u64) {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & 0xffff_f000);
+    let minor = (dev & 0xff) | ((dev >> 12) & 0xffff_ff00);
+    (major, minor)
+}
+
+fn find_locked_file(proc_dir: &Path, pid: u32, lock: &Lock) -> Option<PathBuf> {
+    let fds = fs::read_dir(proc_dir.join(pid.to_string()).join("fd")).ok()?;
+    for fd in fds.flatten() {
+        let metadata = match fs::metadata(fd.path()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.ino() == lock.inode
+            && device_numbers(metadata.dev()) == (lock.major, lock.minor)
+        {
+            return fs::read_link(fd.path()).ok().and_then(fd_target);
+        }
+    }
+    None
+}
+
+// Files on which a process holds an advisory or mandatory lock, along with
+// every directory containing them.
+pub fn locked_file_rules(proc_dir: &Path, action: Action) -> Vec<Rule> {
+    let locks = fs::read_to_string(proc_dir.join("locks")).unwrap_or_default();
+    let mut seen = BTreeSet::new();
+    let mut rules = Vec::new();
+    for lock in locks.lines().filter_map(parse_lock) {
+        // The locked inode is found through the file descriptors of the
+        // process holding the lock, or of any process for OFD locks.
+        let pids = match lock.pid {
+            Some(pid) => vec![pid],
+            None => process_ids(proc_dir),
+        };
+        let found = pids
+            .into_iter()
+            .find_map(|pid| Some((pid, find_locked_file(proc_dir, pid, &lock)?)));
+        if let Some((pid, path)) = found {
+            if seen.insert(path.clone()) {
+                rules.push(Rule {
+                    reason: Some(format!(
+                        "{} is locked ({}) by PID {} ({})",
+                        path.display(),
+                        lock.kind,
+                        pid,
+                        command_name(proc_dir, pid)
+                    )),
+                    action,
+                    guard_ancestors: true,
+                    ..Rule::new(path)
+                });
+            }
+        }
+    }
+    rules
+}
+
+pub const SYS_DIR: &str = "/sys";
+
+// Files which would take the machine down if deleted: active swap files,
+// disk images attached to loop devices and the running kernel.
+pub fn system_file_rules(proc_dir: &Path, sys_dir: &Path, action: Action) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    rules.extend(swap_rules(proc_dir));
+    rules.extend(loop_rules(sys_dir));
+    rules.extend(kernel_rules(proc_dir));
+    for rule in &mut rules {
+        rule.action = action;
+        rule.guard_ancestors = true;
+    }
+    rules
+}
+
+fn swap_rules(proc_dir: &Path) -> Vec<Rule> {
+    // Filename  Type  Size  Used  Priority
+    let swaps = fs::read_to_string(proc_dir.join("swaps")).unwrap_or_default();
+    swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|filename| Rule {
+            reason: Some("active swap space".to_string()),
+            ..Rule::new(PathBuf::from(unescape_octal(filename)))
+        })
+        .collect()
+}
+
+fn loop_rules(sys_dir: &Path) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    let entries = match fs::read_dir(sys_dir.join("block")) {
+        Ok(entries) => entries,
+        Err(_) => return rules,
+    };
+    for entry in entries.flatten() {
+        let device = entry.file_name().to_string_lossy().to_string();
+        if !device.starts_with("loop") {
+            continue;
+        }
+        let backing_file = entry.path().join("loop").join("backing_file");
+        if let Some(path) = fs::read_to_string(backing_file)
+            .ok()
+            .and_then(|contents| fd_target(PathBuf::from(contents.trim_end())))
+        {
+            rules.push(Rule {
+                reason: Some(format!("backing file of /dev/{}", device)),
+                ..Rule::new(path)
+            });
+        }
+    }
+    rules
+}
+
+fn kernel_rules(proc_dir: &Path) -> Vec<Rule> {
+    let release = match fs::read_to_string(proc_dir.join("sys/kernel/osrelease")) {
+        Ok(release) => release.trim().to_string(),
+        Err(_) => return Vec::new(),
+    };
+    if release.is_empty() || release.contains('/') {
+        return Vec::new();
+    }
+    // Naming conventions vary between distributions.
+    [
+        format!("/boot/vmlinuz-{}", release),
+        format!("/boot/initrd.img-{}", release),
+        format!("/boot/initrd-{}", release),
+        format!("/boot/initramfs-{}.img", release),
+        format!("/lib/modules/{}", release),
+        format!("/usr/lib/modules/{}", release),
+    ]
+    .iter()
+    .map(|path| Rule {
+        reason: Some(format!("used by the running kernel ({})", release)),
+        ..Rule::new(PathBuf::from(path))
+    })
+    .collect()
+}
+
+pub const PASSWD_FILE: &str = "/etc/passwd";
+
+// The directory safe-rm is run from. Through guard_ancestors, this also covers
+// every directory above it.
+pub fn working_directory_rule(cwd: &Path, action: Action) -> Rule {
+    Rule {
+        action,
+        reason: Some("current working directory".to_string()),
+        guard_ancestors: true,
+        ..Rule::new(cwd.to_path_buf())
+    }
+}
+
+// The given home directories and those of every account in the password
+// database, along with the directories containing them.
+pub fn home_directory_rules(home_dirs: &[PathBuf], passwd: &Path, action: Action) -> Vec<Rule> {
+    let mut rules: Vec<Rule> = home_dirs
+        .iter()
+        .map(|home| Rule {
+            reason: Some("home directory".to_string()),
+            ..Rule::new(fs::canonicalize(home).unwrap_or_else(|_| home.clone()))
+        })
+        .collect();
+
+    // name:password:uid:gid:gecos:home:shell
+    let accounts = fs::read_to_string(passwd).unwrap_or_default();
+    for fields in accounts
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+    {
+        if let (Some(name), Some(home)) = (fields.first(), fields.get(5)) {
+            if home.is_empty() {
+                continue;
+            }
+            // Accounts without a home directory often point at one that
+            // doesn't exist, like /nonexistent.
+            if let Ok(home) = fs::canonicalize(home) {
+                rules.push(Rule {
+                    reason: Some(format!("home directory of {}", name)),
+                    ..Rule::new(home)
+                });
+            }
+        }
+    }
+    for rule in &mut rules {
+        rule.action = action;
+        rule.guard_ancestors = true;
+    }
+    rules
+}
+
+// Everything directly inside the given home directories, e.g. ~/Documents.
+pub fn home_content_rules(home_dirs: &[PathBuf], action: Action) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    for home in home_dirs
+        .iter()
+        .filter_map(|home| fs::canonicalize(home).ok())
+    {
+        if let Ok(entries) = fs::read_dir(&home) {
+            rules.extend(entries.flatten().map(|entry| Rule {
+                action,
+                reason: Some(format!("top-level entry of {}", home.display())),
+                ..Rule::new(entry.path())
+            }));
+        }
+    }
+    rules
+}
+
+// Decodes the \ooo escapes used for whitespace in files like /proc/swaps.
+pub fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)));
+        match escape {
+            Some(digits) => {
+                let value = digits
+                    .iter()
+                    .fold(0u32, |acc, d| acc * 8 + u32::from(d - b'0'));
+                decoded.push(value as u8);
+                i += 4;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,344 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use tempfile::tempdir;
+
+    #[test]
+    fn fd_target() {
+        use super::super::dynamic::fd_target;
+
+        assert_eq!(
+            fd_target(PathBuf::from("/var/log/syslog")),
+            Some(PathBuf::from("/var/log/syslog"))
+        );
+        assert_eq!(fd_target(PathBuf::from("socket:[12345]")), None);
+        assert_eq!(fd_target(PathBuf::from("pipe:[12345]")), None);
+        assert_eq!(fd_target(PathBuf::from("anon_inode:[eventfd]")), None);
+        assert_eq!(fd_target(PathBuf::from("/tmp/gone (deleted)")), None);
+    }
+
+    #[test]
+    fn mapped_path() {
+        use super::super::dynamic::mapped_path;
+
+        assert_eq!(
+            mapped_path("7f2c4a000000-7f2c4a022000 r--p 00000000 08:01 1234   /usr/lib/libc.so.6"),
+            Some(PathBuf::from("/usr/lib/libc.so.6"))
+        );
+        assert_eq!(
+            mapped_path("55d0c1000000-55d0c1001000 r-xp 00000000 08:01 42 /opt/my app/bin"),
+            Some(PathBuf::from("/opt/my app/bin"))
+        );
+        assert_eq!(
+            mapped_path("7ffd1c000000-7ffd1c021000 rw-p 00000000 00:00 0      [stack]"),
+            None
+        );
+        assert_eq!(
+            mapped_path("7f2c4a000000-7f2c4a022000 rw-p 00000000 00:00 0"),
+            None
+        );
+        assert_eq!(
+            mapped_path("7f2c4a000000-7f2c4a022000 r--p 00000000 08:01 1 /tmp/x (deleted)"),
+            None
+        );
+        assert_eq!(mapped_path(""), None);
+    }
+
+    #[test]
+    fn open_file_rules() {
+        use super::super::dynamic::{open_file_rules, PROC_DIR};
+        use super::super::{matching_rule, Action};
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().canonicalize().unwrap().join("in-use");
+        File::create(&file_path).unwrap();
+
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .stdin(Stdio::from(File::open(&file_path).unwrap()))
+            .spawn()
+            .unwrap();
+        // Wait for the child to have exec'd sleep.
+        let comm = Path::new(PROC_DIR)
+            .join(child.id().to_string())
+            .join("comm");
+        for _ in 0..100 {
+            if fs::read_to_string(&comm).unwrap_or_default().trim_end() == "sleep" {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let rules = open_file_rules(Path::new(PROC_DIR), Action::Confirm);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let rule = matching_rule(&file_path, &rules).unwrap();
+        assert_eq!(rule.action, Action::Confirm);
+        assert_eq!(
+            rule.reason.as_deref().unwrap(),
+            format!(
+                "{} is in use by PID {} (sleep)",
+                file_path.display(),
+                child.id()
+            )
+        );
+
+        // Deleting the containing directory is also prevented.
+        assert!(matching_rule(file_path.parent().unwrap(), &rules).is_some());
+
+        // A missing /proc generates no rules.
+        assert!(open_file_rules(&dir.path().join("missing"), Action::Block).is_empty());
+    }
+
+    #[test]
+    fn unescape_octal() {
+        use super::super::dynamic::unescape_octal;
+
+        assert_eq!(unescape_octal("/swapfile"), "/swapfile");
+        assert_eq!(unescape_octal("/my\\040swap"), "/my swap");
+        assert_eq!(unescape_octal("/tab\\011"), "/tab\t");
+        assert_eq!(unescape_octal("/not\\08"), "/not\\08");
+        assert_eq!(unescape_octal("/short\\04"), "/short\\04");
+        assert_eq!(unescape_octal("/é\\134"), "/é\\");
+    }
+
+    #[test]
+    fn system_file_rules() {
+        use super::super::dynamic::system_file_rules;
+        use super::super::{matching_rule, Action};
+
+        use std::fs;
+        use std::io::Write;
+
+        let proc_dir = tempdir().unwrap();
+        let sys_dir = tempdir().unwrap();
+
+        // Nothing to protect when the files are missing.
+        assert!(system_file_rules(proc_dir.path(), sys_dir.path(), Action::Block).is_empty());
+
+        writeln!(
+            File::create(proc_dir.path().join("swaps")).unwrap(),
+            "Filename\t\t\t\tType\t\tSize\tUsed\tPriority\n\
+             /dev/sda2                               partition\t999420\t0\t-2\n\
+             /var/swap\\040file                      file\t\t1048572\t0\t-3"
+        )
+        .unwrap();
+        fs::create_dir_all(proc_dir.path().join("sys/kernel")).unwrap();
+        writeln!(
+            File::create(proc_dir.path().join("sys/kernel/osrelease")).unwrap(),
+            "6.1.0-13-amd64"
+        )
+        .unwrap();
+        for (device, backing_file) in &[
+            ("loop0", "/srv/images/disk.img"),
+            ("loop1", "/tmp/old.img (deleted)"),
+            ("sda", "/not/a/loop/device"),
+        ] {
+            let loop_dir = sys_dir.path().join("block").join(device).join("loop");
+            fs::create_dir_all(&loop_dir).unwrap();
+            writeln!(
+                File::create(loop_dir.join("backing_file")).unwrap(),
+                "{}",
+                backing_file
+            )
+            .unwrap();
+        }
+        // Unused loop devices have no backing file.
+        fs::create_dir_all(sys_dir.path().join("block/loop2/loop")).unwrap();
+
+        let rules = system_file_rules(proc_dir.path(), sys_dir.path(), Action::Confirm);
+        assert!(rules.iter().all(|rule| rule.action == Action::Confirm));
+
+        let rule = matching_rule(Path::new("/var/swap file"), &rules).unwrap();
+        assert_eq!(rule.reason.as_deref(), Some("active swap space"));
+        assert!(matching_rule(Path::new("/dev/sda2"), &rules).is_some());
+
+        let rule = matching_rule(Path::new("/srv/images"), &rules).unwrap();
+        assert_eq!(rule.reason.as_deref(), Some("backing file of /dev/loop0"));
+        assert!(matching_rule(Path::new("/tmp/old.img"), &rules).is_none());
+        assert!(matching_rule(Path::new("/not/a/loop/device"), &rules).is_none());
+
+        let rule = matching_rule(Path::new("/boot/vmlinuz-6.1.0-13-amd64"), &rules).unwrap();
+        assert_eq!(
+            rule.reason.as_deref(),
+            Some("used by the running kernel (6.1.0-13-amd64)")
+        );
+        assert!(matching_rule(Path::new("/lib/modules"), &rules).is_some());
+        assert!(matching_rule(Path::new("/boot/vmlinuz-6.1.0-12-amd64"), &rules).is_none());
+    }
+
+    #[test]
+    fn parse_lock() {
+        use super::super::dynamic::{parse_lock, Lock};
+
+        assert_eq!(
+            parse_lock("1: POSIX  ADVISORY  WRITE 1234 08:01:1310731 0 EOF"),
+            Some(Lock {
+                kind: "POSIX".to_string(),
+                pid: Some(1234),
+                major: 8,
+                minor: 1,
+                inode: 1310731,
+            })
+        );
+        assert_eq!(
+            parse_lock("7: OFDLCK ADVISORY  READ  -1 fd:1a:42 0 EOF"),
+            Some(Lock {
+                kind: "OFDLCK".to_string(),
+                pid: None,
+                major: 253,
+                minor: 26,
+                inode: 42,
+            })
+        );
+        assert_eq!(
+            parse_lock("1: -> POSIX  ADVISORY  WRITE 1235 08:01:1310731 0 EOF"),
+            None
+        );
+        assert_eq!(
+            parse_lock("1: FLOCK ADVISORY WRITE 12 not-a-device 0 EOF"),
+            None
+        );
+        assert_eq!(parse_lock(""), None);
+    }
+
+    #[test]
+    fn device_numbers() {
+        use super::super::dynamic::device_numbers;
+
+        assert_eq!(device_numbers(0x801), (8, 1));
+        assert_eq!(device_numbers(0xfd1a), (253, 26));
+        assert_eq!(device_numbers(0x1_2000_6783_45ab), (0x1_2345, 0x6_78ab));
+    }
+
+    #[test]
+    fn locked_file_rules() {
+        use super::super::dynamic::{locked_file_rules, PROC_DIR};
+        use super::super::{matching_rule, Action};
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().canonicalize().unwrap().join("db.lock");
+        let unlocked_path = dir.path().canonicalize().unwrap().join("unlocked");
+        let file = File::create(&file_path).unwrap();
+        let _unlocked = File::create(&unlocked_path).unwrap();
+
+        file.lock().unwrap();
+        let rules = locked_file_rules(Path::new(PROC_DIR), Action::Block);
+        file.unlock().unwrap();
+
+        let rule = matching_rule(&file_path, &rules).unwrap();
+        assert_eq!(rule.action, Action::Block);
+        assert!(rule.reason.as_deref().unwrap().starts_with(&format!(
+            "{} is locked (FLOCK) by PID {} (",
+            file_path.display(),
+            std::process::id()
+        )));
+        assert!(matching_rule(&unlocked_path, &rules).is_none());
+
+        // Deleting the containing directory is also prevented.
+        assert!(matching_rule(file_path.parent().unwrap(), &rules).is_some());
+
+        // Nothing is locked without /proc/locks.
+        assert!(locked_file_rules(dir.path(), Action::Block).is_empty());
+    }
+
+    #[test]
+    fn working_directory_rule() {
+        use super::super::dynamic::working_directory_rule;
+        use super::super::{matching_rule, Action};
+
+        let rules = vec![working_directory_rule(
+            Path::new("/srv/project/src"),
+            Action::Confirm,
+        )];
+        for path in &["/srv/project/src", "/srv/project", "/srv", "/"] {
+            let rule = matching_rule(Path::new(path), &rules).unwrap();
+            assert_eq!(rule.action, Action::Confirm);
+            assert_eq!(rule.reason.as_deref(), Some("current working directory"));
+        }
+        assert!(matching_rule(Path::new("/srv/project/src/main.rs"), &rules).is_none());
+        assert!(matching_rule(Path::new("/srv/other"), &rules).is_none());
+    }
+
+    #[test]
+    fn home_directory_rules() {
+        use super::super::dynamic::home_directory_rules;
+        use super::super::{matching_rule, Action};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let (alice, bob) = (root.join("home/alice"), root.join("home/bob"));
+        fs::create_dir_all(&alice).unwrap();
+        fs::create_dir_all(&bob).unwrap();
+        let passwd = root.join("passwd");
+        fs::write(
+            &passwd,
+            format!(
+                "alice:x:1000:1000:Alice,,,:{}:/bin/bash\n\
+                 nobody:x:65534:65534:nobody:/nonexistent:/usr/sbin/nologin\n\
+                 broken\n\
+                 empty:x:1001:1001:::/bin/sh\n",
+                alice.display()
+            ),
+        )
+        .unwrap();
+
+        let rules = home_directory_rules(std::slice::from_ref(&bob), &passwd, Action::Block);
+        assert_eq!(rules.len(), 2);
+        let rule = matching_rule(&alice, &rules).unwrap();
+        assert_eq!(rule.action, Action::Block);
+        assert_eq!(rule.reason.as_deref(), Some("home directory of alice"));
+        let rule = matching_rule(&bob, &rules).unwrap();
+        assert_eq!(rule.reason.as_deref(), Some("home directory"));
+        // The directories above them are protected too.
+        assert!(matching_rule(&root.join("home"), &rules).is_some());
+        assert!(matching_rule(&alice.join("notes.txt"), &rules).is_none());
+        assert!(matching_rule(Path::new("/nonexistent"), &rules).is_none());
+
+        // Missing password database
+        let rules = home_directory_rules(&[], &root.join("missing"), Action::Block);
+        assert!(rules.is_empty());
+    }
+
+    #[test]
+    fn home_content_rules() {
+        use super::super::dynamic::home_content_rules;
+        use super::super::{matching_rule, Action};
+
+        let dir = tempdir().unwrap();
+        let home = dir.path().canonicalize().unwrap();
+        fs::create_dir(home.join("Documents")).unwrap();
+        fs::create_dir(home.join("Documents/old")).unwrap();
+        File::create(home.join(".bashrc")).unwrap();
+
+        let rules = home_content_rules(&[home.clone(), home.join("missing")], Action::Confirm);
+        assert_eq!(rules.len(), 2);
+        for path in &["Documents", ".bashrc"] {
+            let rule = matching_rule(&home.join(path), &rules).unwrap();
+            assert_eq!(rule.action, Action::Confirm);
+            assert_eq!(
+                rule.reason,
+                Some(format!("top-level entry of {}", home.display()))
+            );
+        }
+        assert!(matching_rule(&home.join("Documents/old"), &rules).is_none());
+        assert!(matching_rule(&home, &rules).is_none());
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Checks on the whole command line for the typical results of a shell
+// variable being empty or unset, like "rm -rf $PREFIX/*" expanding to every
+// directory under /. Individual paths may not be protected, but together
+// they are very unlikely to be what was meant.
+
+use std::collections::BTreeSet;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use super::normalize_path;
+use super::options::{self, Arg};
+
+// Directories smaller than this are too easy to empty on purpose.
+const MIN_SIBLINGS: usize = 2;
+
+// The arguments which rm will treat as paths.
+pub fn operands(args: &[OsString]) -> Vec<OsString> {
+    options::parse(args)
+        .into_iter()
+        .filter_map(|arg| match arg {
+            Arg::Operand(operand) => Some(operand),
+            _ => None,
+        })
+        .collect()
+}
+
+// Returns why the command line looks like a shell expansion accident, if it
+// does. Sibling checks apply to / and the given home directories.
+pub fn suspicious_arguments(args: &[OsString], home_dirs: &[PathBuf]) -> Option<String> {
+    let operands = operands(args);
+    let paths: Vec<PathBuf> = operands
+        .iter()
+        .map(|arg| PathBuf::from(normalize_path(arg)))
+        .collect();
+
+    if let Some(arg) = operands.iter().find(|arg| starts_with_variable(arg)) {
+        return Some(format!(
+            "{} looks like an unexpanded shell variable",
+            arg.to_string_lossy()
+        ));
+    }
+    if paths.iter().any(|path| path == Path::new("/")) {
+        return Some("/ would be removed, as if a shell variable before / was empty".to_string());
+    }
+
+    // Repeated files are common, as in "rm -f main.o prog *.o", but not
+    // repeated directories.
+    let distinct: BTreeSet<&PathBuf> = paths.iter().collect();
+    if distinct.len() < paths.len() {
+        let repeated = paths
+            .iter()
+            .filter(|path| path.is_dir())
+            .find(|path| paths.iter().filter(|p| p == path).count() > 1);
+        if let Some(repeated) = repeated {
+            return Some(format!(
+                "{} is given more than once, as if shell variables in the arguments were empty",
+                repeated.display()
+            ));
+        }
+    }
+    let mut dirs = vec![PathBuf::from("/")];
+    dirs.extend(home_dirs.iter().filter_map(|dir| dir.canonicalize().ok()));
+    for dir in dirs {
+        let children = visible_children(&dir);
+        if children.len() >= MIN_SIBLINGS && children.iter().all(|child| distinct.contains(child)) {
+            return Some(format!(
+                "the arguments cover everything in {}, as if a shell variable before /* was empty",
+                dir.display()
+            ));
+        }
+    }
+    None
+}
+
+// "$NAME/" or "${NAME}/" left unexpanded, e.g. because of single quotes.
+// Other dollar signs are common in file names, like Outer$Inner.class.
+fn starts_with_variable(arg: &OsStr) -> bool {
+    let rest = match arg.as_bytes().strip_prefix(b"$") {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let (name, rest) = match rest.strip_prefix(b"{") {
+        Some(braced) => match braced.iter().position(|&c| c == b'}') {
+            Some(end) => (&braced[..end], &braced[end + 1..]),
+            None => return false,
+        },
+        None => {
+            let end = rest
+                .iter()
+                .position(|&c| c != b'_' && !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            rest.split_at(end)
+        }
+    };
+    let valid_name = name
+        .first()
+        .is_some_and(|&c| c == b'_' || c.is_ascii_alphabetic())
+        && name.iter().all(|&c| c == b'_' || c.is_ascii_alphanumeric());
+    valid_name && rest.starts_with(b"/")
+}
+
+// What "DIR/*" expands to, since shells skip hidden files.
+fn visible_children(dir: &Path) -> Vec<PathBuf> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| !entry.file_name().as_bytes().starts_with(b"."))
+            .map(|entry| entry.path())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
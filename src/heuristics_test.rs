@@ -0,0 +1,107 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::args;
+    use std::fs::{self, File};
+    use tempfile::tempdir;
+
+    #[test]
+    fn operands() {
+        use super::super::heuristics::operands;
+
+        assert_eq!(
+            operands(&args(&["-rf", "a", "-", "--verbose", "--", "-b"])),
+            vec!["a", "-", "-b"]
+        );
+        assert!(operands(&args(&["-r"])).is_empty());
+    }
+
+    #[test]
+    fn suspicious_arguments() {
+        use super::super::heuristics::suspicious_arguments;
+
+        let dir = tempdir().unwrap();
+        let home = dir.path().canonicalize().unwrap();
+        for name in ["Documents", "Music", ".profile"] {
+            fs::create_dir(home.join(name)).unwrap();
+        }
+        File::create(home.join("notes.txt")).unwrap();
+        let in_home = |name: &str| home.join(name).to_str().unwrap().to_string();
+        let home_dirs = vec![home.clone()];
+        let check = |words: &[&str]| suspicious_arguments(&args(words), &home_dirs);
+
+        assert_eq!(check(&["-rf", &in_home("Music")]), None);
+        assert_eq!(
+            check(&["-rf", &in_home("Music"), &in_home("notes.txt")]),
+            None
+        );
+        assert_eq!(check(&["-rf"]), None);
+
+        // The root directory
+        assert_eq!(
+            check(&["-rf", "/"]).unwrap(),
+            "/ would be removed, as if a shell variable before / was empty"
+        );
+        assert!(check(&["-rf", "//."]).is_some());
+
+        // Unexpanded variables
+        assert_eq!(
+            check(&["-rf", "$BUILD_DIR/"]).unwrap(),
+            "$BUILD_DIR/ looks like an unexpanded shell variable"
+        );
+        assert!(check(&["${PREFIX}/lib"]).is_some());
+        assert_eq!(check(&["price$5.txt"]), None);
+        assert_eq!(check(&["-f", "Outer$Inner.class", "$Cache.class"]), None);
+        assert_eq!(check(&["$HOME", "${PREFIX", "$1/lib", "${}/lib"]), None);
+
+        // Everything in a directory
+        let everything = [
+            "-rf",
+            &in_home("Documents"),
+            &in_home("Music"),
+            &in_home("notes.txt"),
+        ];
+        assert_eq!(
+            check(&everything).unwrap(),
+            format!(
+                "the arguments cover everything in {}, as if a shell variable before /* was empty",
+                home.display()
+            )
+        );
+        let everything_in_root: Vec<String> = fs::read_dir("/")
+            .unwrap()
+            .flatten()
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .map(|entry| entry.path().to_str().unwrap().to_string())
+            .collect();
+        let everything_in_root: Vec<&str> = everything_in_root.iter().map(|s| s.as_str()).collect();
+        assert!(suspicious_arguments(&args(&everything_in_root), &[]).is_some());
+
+        // Repeated paths
+        assert_eq!(
+            check(&[&in_home("Music"), &in_home("Music/")]).unwrap(),
+            format!(
+                "{} is given more than once, as if shell variables in the arguments were empty",
+                in_home("Music")
+            )
+        );
+        assert_eq!(
+            check(&["-f", &in_home("notes.txt"), &in_home("notes.txt")]),
+            None
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Under sudo, pkexec or doas, safe-rm runs as root on behalf of another user
+// whose rules should still apply and who should show up in the audit log.
+
+use nix::unistd::{self, Uid, User};
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq)]
+pub struct Identity {
+    pub name: String,
+    pub uid: u32,
+    pub home: PathBuf,
+}
+
+impl From<User> for Identity {
+    fn from(user: User) -> Identity {
+        Identity {
+            name: user.name,
+            uid: user.uid.as_raw(),
+            home: user.dir,
+        }
+    }
+}
+
+pub fn effective_uid() -> u32 {
+    unistd::geteuid().as_raw()
+}
+
+// Whether the process runs with privileges it didn't start with, through a
+// setuid or setgid bit, or on behalf of another user.
+pub fn is_privileged() -> bool {
+    unistd::getuid() != unistd::geteuid()
+        || unistd::getgid() != unistd::getegid()
+        || std::env::var_os("SUDO_USER").is_some()
+        || invoking_user().is_some()
+}
+
+pub fn current_user() -> Option<Identity> {
+    User::from_uid(unistd::geteuid()).ok()?.map(Identity::from)
+}
+
+// A user given by name or uid.
+pub fn lookup_user(user: &str) -> Option<Identity> {
+    let user = match user.parse() {
+        Ok(uid) => User::from_uid(Uid::from_raw(uid)),
+        Err(_) => User::from_name(user),
+    };
+    user.ok()?.map(Identity::from)
+}
+
+// The user root is acting on behalf of, if any.
+pub fn invoking_user() -> Option<Identity> {
+    if effective_uid() != 0 {
+        return None;
+    }
+    invoking_user_from(|name| std::env::var(name).ok())
+}
+
+pub fn invoking_user_from(var: impl Fn(&str) -> Option<String>) -> Option<Identity> {
+    let user = if let Some(name) = var("SUDO_USER") {
+        User::from_name(&name)
+    } else if let Some(uid) = var("PKEXEC_UID") {
+        match uid.parse() {
+            Ok(uid) => User::from_uid(Uid::from_raw(uid)),
+            Err(_) => return None,
+        }
+    } else if let Some(name) = var("DOAS_USER") {
+        User::from_name(&name)
+    } else {
+        return None;
+    };
+    user.ok()?
+        .map(Identity::from)
+        .filter(|identity| identity.uid != 0)
+}
@@ -0,0 +1,50 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn invoking_user_from() {
+        use super::super::identity::{invoking_user_from, Identity};
+
+        let invoking_user = |vars: &[(&str, &str)]| {
+            let vars: HashMap<&str, &str> = vars.iter().cloned().collect();
+            invoking_user_from(|name| vars.get(name).map(|value| value.to_string()))
+        };
+        let daemon = Identity {
+            name: "daemon".to_string(),
+            uid: 1,
+            home: PathBuf::from("/usr/sbin"),
+        };
+
+        assert_eq!(invoking_user(&[]), None);
+        assert_eq!(invoking_user(&[("SUDO_USER", "daemon")]).unwrap(), daemon);
+        assert_eq!(invoking_user(&[("PKEXEC_UID", "1")]).unwrap(), daemon);
+        assert_eq!(invoking_user(&[("DOAS_USER", "daemon")]).unwrap(), daemon);
+        // sudo takes precedence.
+        assert_eq!(
+            invoking_user(&[("SUDO_USER", "daemon"), ("DOAS_USER", "nobody")]).unwrap(),
+            daemon
+        );
+
+        // Root isn't acting on behalf of anybody else.
+        assert_eq!(invoking_user(&[("SUDO_USER", "root")]), None);
+        assert_eq!(invoking_user(&[("SUDO_USER", "no-such-user")]), None);
+        assert_eq!(invoking_user(&[("PKEXEC_UID", "daemon")]), None);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Project jails: below a directory containing a .safe-rm-root marker, or when
+// SAFE_RM_JAIL names a directory, nothing outside of that directory may be
+// deleted. This keeps build scripts and Makefiles from cleaning up more than
+// their own tree when a path is computed wrongly.
+
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+use super::heuristics;
+use super::normalize_path;
+
+pub const MARKER: &str = ".safe-rm-root";
+pub const JAIL_VAR: &str = "SAFE_RM_JAIL";
+
+// Returns the directory deletions are confined to, if any. The environment
+// variable takes precedence over markers, of which the nearest one wins.
+pub fn jail_root(cwd: &Path, jail_var: Option<OsString>) -> Option<PathBuf> {
+    if let Some(root) = jail_var.filter(|root| !root.is_empty()) {
+        return Some(absolute_path(&cwd.join(root)));
+    }
+    cwd.ancestors()
+        .find(|dir| dir.join(MARKER).exists())
+        .map(Path::to_path_buf)
+}
+
+// Returns why the command line reaches outside of the jail, if it does.
+pub fn escaping_operand(args: &[OsString], root: &Path, cwd: &Path) -> Option<String> {
+    heuristics::operands(args).into_iter().find_map(|arg| {
+        let path = absolute_path(&cwd.join(&arg));
+        if path.starts_with(root) {
+            None
+        } else {
+            Some(format!(
+                "{} is outside of the project root {}",
+                arg.to_string_lossy(),
+                root.display()
+            ))
+        }
+    })
+}
+
+// Paths which don't exist can't be canonicalized, so any "." and ".." left
+// are resolved without looking at the filesystem.
+fn absolute_path(path: &Path) -> PathBuf {
+    let mut absolute = PathBuf::new();
+    for component in Path::new(&normalize_path(path.as_os_str())).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            other => absolute.push(other),
+        }
+    }
+    absolute
+}
@@ -0,0 +1,99 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::args;
+    use std::ffi::OsString;
+    use std::fs::{self, File};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn jail_root() {
+        use super::super::jail::{jail_root, MARKER};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let project = root.join("project");
+        let src = project.join("src");
+        fs::create_dir_all(&src).unwrap();
+        assert_eq!(jail_root(&src, None), None);
+
+        File::create(project.join(MARKER)).unwrap();
+        assert_eq!(jail_root(&src, None), Some(project.clone()));
+        assert_eq!(jail_root(&project, None), Some(project.clone()));
+        assert_eq!(jail_root(&root, None), None);
+
+        // The nearest marker wins.
+        File::create(src.join(MARKER)).unwrap();
+        assert_eq!(jail_root(&src, None), Some(src.clone()));
+
+        // The environment variable takes precedence.
+        assert_eq!(
+            jail_root(&src, Some(OsString::from(&root))),
+            Some(root.clone())
+        );
+        assert_eq!(
+            jail_root(&src, Some(OsString::from(".."))),
+            Some(project.clone())
+        );
+        assert_eq!(jail_root(&src, Some(OsString::new())), Some(src));
+    }
+
+    #[test]
+    fn escaping_operand() {
+        use super::super::jail::escaping_operand;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let project = root.join("project");
+        let build = project.join("build");
+        fs::create_dir_all(&build).unwrap();
+        File::create(root.join("outside")).unwrap();
+        std::os::unix::fs::symlink(root.join("outside"), build.join("link")).unwrap();
+
+        let inside = [
+            build.to_str().unwrap(),
+            "missing",
+            "build/../missing",
+            "./build/link",
+            "--",
+            "-rf",
+        ];
+        assert_eq!(escaping_operand(&args(&["-rf"]), &project, &project), None);
+        assert_eq!(escaping_operand(&args(&inside), &project, &project), None);
+
+        for outside in &["..", "../outside", "build/../../outside", "/"] {
+            assert_eq!(
+                escaping_operand(&args(&["-rf", outside]), &project, &project),
+                Some(format!(
+                    "{} is outside of the project root {}",
+                    outside,
+                    project.display()
+                ))
+            );
+        }
+        // Symlinked directories are followed.
+        let alias = root.join("alias");
+        std::os::unix::fs::symlink(&root, &alias).unwrap();
+        assert!(escaping_operand(
+            &args(&[alias.join("outside").to_str().unwrap()]),
+            &project,
+            Path::new("/")
+        )
+        .is_some());
+    }
+}
@@ -15,17 +15,57 @@
 
 #![forbid(unsafe_code)]
 
+mod audit;
+mod audit_test;
+mod child;
+mod child_test;
+mod classifier;
+mod classifier_test;
+mod commands;
+mod commands_test;
+mod dynamic;
+mod dynamic_test;
+mod heuristics;
+mod heuristics_test;
+mod identity;
+mod identity_test;
+mod jail;
+mod jail_test;
 mod main_test;
+mod markers;
+mod markers_test;
+mod options;
+mod options_test;
+mod ownership;
+mod ownership_test;
+mod policy;
+mod policy_test;
+mod predicates;
+mod predicates_test;
+mod realrm;
+mod realrm_test;
+mod rulefiles;
+mod rulefiles_test;
+mod session;
+mod session_test;
+mod test_helpers;
+mod unlock;
+mod unlock_test;
+mod xattrs;
+mod xattrs_test;
 
 use glob::glob;
 use std::ffi::{OsStr, OsString};
 use std::fs::{self, File};
 use std::io::{self, BufRead};
+use std::os::unix::fs::MetadataExt;
 use std::path::{self, Path, PathBuf};
 use std::process;
 
+use audit::AuditLog;
 use serde_derive::Deserialize;
 use std::io::prelude::*;
+use unlock::Unlocks;
 
 const GLOBAL_CONFIG: &str = "/etc/safe-rm.conf";
 const LOCAL_GLOBAL_CONFIG: &str = "/usr/local/etc/safe-rm.conf";
@@ -34,11 +74,170 @@ const LEGACY_USER_CONFIG: &str = ".safe-rm";
 
 const REAL_RM: &str = "/bin/rm";
 
+const TTY: &str = "/dev/tty";
+
 const SAFE_RM_CONFIG: &str = "/etc/safe-rm.toml";
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct Config {
     rm_binary: Option<String>,
+    // Program and leading arguments, e.g. ["busybox", "rm"].
+    rm_command: Option<Vec<String>>,
+    rm_dialect: Option<realrm::Dialect>,
+    // How the SAFE_RM_REAL_RM environment variable is treated.
+    real_rm_override: Option<realrm::EnvOverride>,
+    #[serde(default)]
+    real_rm_override_dirs: Vec<PathBuf>,
+    // Expected SHA-256 digest of the real rm binary.
+    rm_binary_sha256: Option<String>,
+    audit_log: Option<String>,
+    // Protect files that running processes have open or mapped.
+    open_files: Option<Action>,
+    // Protect swap files, loop device backing files and the running kernel.
+    system_files: Option<Protection>,
+    // Protect files locked by running processes.
+    locked_files: Option<Protection>,
+    // Protect the current working directory and the directories above it.
+    working_directory: Option<Protection>,
+    // Protect $HOME and the home directory of every account.
+    home_directories: Option<Protection>,
+    // Protect the entries directly inside $HOME.
+    home_contents: Option<Action>,
+    // Protect private keys, password databases and other sensitive files
+    // recognised from their contents.
+    sensitive_files: Option<Action>,
+    // Protect files owned by other users when running through sudo.
+    other_users: Option<ownership::OtherUsers>,
+    // Options to refuse, remove or add.
+    flags: Option<options::FlagPolicy>,
+    // Protected paths, optionally limited to a window of time.
+    #[serde(default)]
+    rules: Vec<policy::PolicyRule>,
+}
+
+// What to do when an argument matches a protected path. The variants are
+// ordered from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    Warn,
+    Confirm,
+    Block,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Option<Action> {
+        match name {
+            "warn" => Some(Action::Warn),
+            "confirm" => Some(Action::Confirm),
+            "block" => Some(Action::Block),
+            _ => None,
+        }
+    }
+}
+
+// How a protection which is on by default applies: "off" or an action.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Protection {
+    Off,
+    Warn,
+    Confirm,
+    Block,
+}
+
+impl Protection {
+    // Protections which aren't set block.
+    fn action(setting: Option<Protection>) -> Option<Action> {
+        match setting.unwrap_or(Protection::Block) {
+            Protection::Off => None,
+            Protection::Warn => Some(Action::Warn),
+            Protection::Confirm => Some(Action::Confirm),
+            Protection::Block => Some(Action::Block),
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Action::Warn => "warn",
+            Action::Confirm => "confirm",
+            Action::Block => "block",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Rule {
+    path: PathBuf,
+    action: Action,
+    reason: Option<String>,
+    owner: Option<String>,
+    // Whether deleting a directory containing this path is also prevented.
+    guard_ancestors: bool,
+    // Metadata conditions which, when present, make the rule apply to
+    // matching files anywhere below the path instead of the path itself.
+    conditions: Vec<predicates::Predicate>,
+    // Paths below the rule's path which conditional rules don't apply to.
+    exceptions: Vec<PathBuf>,
+    // Whether the rule comes from the system configuration or safe-rm
+    // itself rather than from a user.
+    system: bool,
+    // Whether rules from users may not weaken this system rule.
+    enforced: bool,
+}
+
+impl Rule {
+    fn new(path: PathBuf) -> Rule {
+        Rule {
+            path,
+            action: Action::Block,
+            reason: None,
+            owner: None,
+            guard_ancestors: false,
+            conditions: Vec::new(),
+            exceptions: Vec::new(),
+            system: false,
+            enforced: false,
+        }
+    }
+
+    // Attributes a rule found elsewhere, like inside a directory being
+    // deleted, to the given path.
+    fn inherited_by(mut self, path: &Path) -> Rule {
+        if self.path != path {
+            self.reason = Some(match self.reason.take() {
+                Some(reason) => format!("{} is protected: {}", self.path.display(), reason),
+                None => format!("{} is protected", self.path.display()),
+            });
+            self.path = path.to_path_buf();
+        }
+        self
+    }
+
+    // Among equally severe rules, those which users can't weaken or unlock
+    // come first.
+    fn severity(&self) -> (Action, bool, bool) {
+        (self.action, self.system, self.enforced)
+    }
+
+    // Only root can override this rule, so there is no need to look for
+    // others, e.g. inside large directories.
+    fn is_final(&self) -> bool {
+        self.action == Action::Block && self.system
+    }
+
+    // Human-readable explanation suitable for appending to a message.
+    fn details(&self) -> String {
+        match (&self.reason, &self.owner) {
+            (Some(reason), Some(owner)) => format!(": {} (owner: {})", reason, owner),
+            (Some(reason), None) => format!(": {}", reason),
+            (None, Some(owner)) => format!(" (owner: {})", owner),
+            (None, None) => String::new(),
+        }
+    }
 }
 
 const DEFAULT_PATHS: &[&str] = &[
@@ -72,12 +271,12 @@ const DEFAULT_PATHS: &[&str] = &[
 
 const MAX_GLOB_EXPANSION: usize = 256;
 
-fn read_config<P: AsRef<Path>>(filename: P) -> Option<Vec<PathBuf>> {
-    let mut paths = Vec::new();
+fn read_config<P: AsRef<Path>>(filename: P) -> Option<Vec<Rule>> {
+    let mut rules = Vec::new();
     if !filename.as_ref().exists() {
         // Not all config files are expected to be present.
         // If they're missing, we silently skip them.
-        return Some(paths);
+        return Some(rules);
     }
     let f = File::open(&filename).ok().or_else(|| {
         println!(
@@ -87,13 +286,67 @@ fn read_config<P: AsRef<Path>>(filename: P) -> Option<Vec<PathBuf>> {
         None
     })?;
 
+    // Annotations such as "# action: confirm" apply to the next pattern.
+    let mut annotations = Rule::new(PathBuf::new());
     let reader = io::BufReader::new(f);
     for line_result in reader.lines() {
+        if let Ok(line) = &line_result {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_annotation(line) {
+                Some(("when", value)) => match predicates::parse_conditions(value) {
+                    Ok(conditions) => annotations.conditions = conditions,
+                    Err(e) => println!(
+                        "safe-rm: Invalid condition \"{}\" found in {} and ignored: {}.",
+                        value,
+                        filename.as_ref().display(),
+                        e
+                    ),
+                },
+                Some((key, value)) => {
+                    // Unknown annotations are plain comments.
+                    apply_annotation(&mut annotations, key, value, filename.as_ref().display());
+                }
+                None => (),
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+        }
         if let Some(line_paths) = parse_line(filename.as_ref().display(), line_result) {
-            paths.extend(line_paths.into_iter());
+            rules.extend(line_paths.into_iter().map(|path| Rule {
+                path,
+                ..annotations.clone()
+            }));
         }
+        annotations = Rule::new(PathBuf::new());
     }
-    Some(paths)
+    Some(rules)
+}
+
+// Returns whether the annotation was recognised.
+fn apply_annotation(rule: &mut Rule, key: &str, value: &str, filename: path::Display) -> bool {
+    match key {
+        "action" => match Action::from_name(value) {
+            Some(action) => rule.action = action,
+            None => println!(
+                "safe-rm: Invalid action \"{}\" found in {} and ignored.",
+                value, filename
+            ),
+        },
+        "reason" => rule.reason = Some(value.to_string()),
+        "owner" => rule.owner = Some(value.to_string()),
+        "enforced" => rule.enforced = value == "yes" || value == "true",
+        _ => return false,
+    }
+    true
+}
+
+fn parse_annotation(line: &str) -> Option<(&str, &str)> {
+    let comment = line.strip_prefix('#')?;
+    let (key, value) = comment.split_once(':')?;
+    Some((key.trim(), value.trim()))
 }
 
 fn parse_line(filename: path::Display, line_result: io::Result<String>) -> Option<Vec<PathBuf>> {
@@ -146,23 +399,17 @@ fn symlink_canonicalize(path: &Path) -> Option<PathBuf> {
     // 2. canonicalizing the directory
     // 3. recombining directory and file name
     let parent: Option<PathBuf> = match explicit_path.parent() {
-        Some(dir) => match dir.canonicalize() {
-            Ok(normalized_parent) => Some(normalized_parent),
-            Err(_) => None,
-        },
+        Some(dir) => dir.canonicalize().ok(),
         None => Some(PathBuf::from("/")),
     };
-    return match parent {
-        Some(dir) => match path.file_name() {
-            Some(file_name) => Some(dir.join(file_name)),
-            None => match dir.parent() {
-                // file_name == ".."
-                Some(parent_dir) => Some(parent_dir.to_path_buf()),
-                None => Some(PathBuf::from("/")), // Stop at the root.
-            },
+    parent.map(|dir| match path.file_name() {
+        Some(file_name) => dir.join(file_name),
+        None => match dir.parent() {
+            // file_name == ".."
+            Some(parent_dir) => parent_dir.to_path_buf(),
+            None => PathBuf::from("/"), // Stop at the root.
         },
-        None => None,
-    };
+    })
 }
 
 fn normalize_path(arg: &OsStr) -> OsString {
@@ -171,7 +418,7 @@ fn normalize_path(arg: &OsStr) -> OsString {
     // Handle symlinks.
     if let Ok(metadata) = path.symlink_metadata() {
         if metadata.file_type().is_symlink() {
-            return match symlink_canonicalize(&path) {
+            return match symlink_canonicalize(path) {
                 Some(normalized_path) => normalized_path.into_os_string(),
                 None => OsString::from(arg),
             };
@@ -185,41 +432,400 @@ fn normalize_path(arg: &OsStr) -> OsString {
     }
 }
 
+// Returns the most severe rule protecting the given path, if any.
+fn matching_rule<'a>(path: &Path, rules: &'a [Rule]) -> Option<&'a Rule> {
+    rules
+        .iter()
+        .filter(|rule| rule.conditions.is_empty())
+        .filter(|rule| rule.path == path || (rule.guard_ancestors && rule.path.starts_with(path)))
+        .max_by_key(|rule| rule.severity())
+}
+
+// Returns the most severe rule whose metadata conditions match the given
+// path, if any.
+fn conditional_rule(path: &Path, metadata: &fs::Metadata, rules: &[Rule]) -> Option<Rule> {
+    let rule = rules
+        .iter()
+        .filter(|rule| !rule.conditions.is_empty() && path.starts_with(&rule.path))
+        .filter(|rule| !rule.exceptions.iter().any(|e| path.starts_with(e)))
+        .filter(|rule| rule.conditions.iter().all(|c| c.matches(path, metadata)))
+        .max_by_key(|rule| rule.severity())?;
+    let conditions: Vec<String> = rule.conditions.iter().map(|c| c.to_string()).collect();
+    Some(Rule {
+        path: path.to_path_buf(),
+        reason: Some(match &rule.reason {
+            Some(reason) => reason.clone(),
+            None => format!(
+                "matches \"{}\" under {}",
+                conditions.join(" and "),
+                rule.path.display()
+            ),
+        }),
+        conditions: Vec::new(),
+        exceptions: Vec::new(),
+        ..rule.clone()
+    })
+}
+
+// Like matching_rule() but also takes into account protections which depend
+// on the path itself, like marker files.
+fn protecting_rule(path: &Path, rules: &[Rule]) -> Option<Rule> {
+    let rule = matching_rule(path, rules).cloned();
+    if rule.as_ref().is_some_and(Rule::is_final) {
+        return rule;
+    }
+    let mut candidates = vec![
+        rule,
+        markers::marker_rule(path),
+        rulefiles::rule_file_rule(path),
+        xattrs::xattr_rule(path),
+    ];
+    if let Ok(metadata) = path.symlink_metadata() {
+        candidates.push(conditional_rule(path, &metadata, rules));
+        // Deleting a symlink doesn't affect the directory it points to.
+        if metadata.is_dir() {
+            candidates.push(contents_rule(path, rules));
+        }
+    }
+    candidates
+        .into_iter()
+        .flatten()
+        .max_by_key(|rule| rule.severity())
+}
+
+// Protections found inside a directory, which a recursive deletion of that
+// directory would remove as well.
+fn contents_rule(dir: &Path, rules: &[Rule]) -> Option<Rule> {
+    let mut rule_files = rulefiles::RuleFileCache::default();
+    let mut most_severe: Option<Rule> = None;
+    find_in_tree(dir, &mut |entry: &Path, metadata: &fs::Metadata| {
+        let marker = if metadata.is_dir() {
+            markers::read_marker(entry).map(|marker| marker.rule)
+        } else {
+            None
+        };
+        let candidates = vec![
+            marker,
+            rule_files.matching_rule(entry, metadata.is_dir()),
+            xattrs::xattr_rule(entry),
+            conditional_rule(entry, metadata, rules),
+        ];
+        for rule in candidates.into_iter().flatten() {
+            if most_severe
+                .as_ref()
+                .is_none_or(|found| rule.severity() > found.severity())
+            {
+                most_severe = Some(rule);
+            }
+        }
+        // No need to look any further once nothing more severe can be found.
+        most_severe
+            .as_ref()
+            .filter(|rule| rule.is_final())
+            .map(|_| ())
+    });
+    most_severe.map(|rule| rule.inherited_by(dir))
+}
+
+// A rule which only root can unlock, protecting the path or anything inside
+// it, preferably an enforced one which can't be unlocked at all. Tokens for
+// a weaker rule mustn't bypass it.
+fn root_only_rule(path: &Path, rules: &[Rule]) -> Option<Rule> {
+    let rules: Vec<Rule> = rules
+        .iter()
+        .filter(|rule| rule.system && rule.action != Action::Warn)
+        .cloned()
+        .collect();
+    let mut found = matching_rule(path, &rules).cloned();
+    let mut check = |entry: &Path, metadata: &fs::Metadata| {
+        if let Some(rule) = conditional_rule(entry, metadata, &rules) {
+            if found
+                .as_ref()
+                .is_none_or(|found| rule.enforced && !found.enforced)
+            {
+                found = Some(rule.inherited_by(path));
+            }
+        }
+        found.as_ref().filter(|rule| rule.enforced).map(|_| ())
+    };
+    if let Ok(metadata) = path.symlink_metadata() {
+        if check(path, &metadata).is_none()
+            && metadata.is_dir()
+            && rules.iter().any(|rule| !rule.conditions.is_empty())
+        {
+            find_in_tree(path, &mut check);
+        }
+    }
+    found
+}
+
+// Depth-first search of the contents of a directory, without following
+// symlinks. Unreadable directories are skipped.
+fn find_in_tree<T>(
+    dir: &Path,
+    check: &mut impl FnMut(&Path, &fs::Metadata) -> Option<T>,
+) -> Option<T> {
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        let metadata = match path.symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if let Some(found) = check(&path, &metadata) {
+            return Some(found);
+        }
+        if metadata.is_dir() {
+            if let Some(found) = find_in_tree(&path, check) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+fn prompt_yes_no(mut input: impl BufRead, mut output: impl Write, question: &str) -> bool {
+    if write!(output, "{} ", question).is_err() || output.flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if input.read_line(&mut answer).is_err() {
+        return false;
+    }
+    answer.trim_start().starts_with(['y', 'Y'])
+}
+
+fn confirm(question: &str) -> bool {
+    // Ask on the controlling terminal so that redirected input cannot answer
+    // on the user's behalf. Without a terminal, the deletion is refused.
+    match fs::OpenOptions::new().read(true).write(true).open(TTY) {
+        Ok(tty) => match tty.try_clone() {
+            Ok(output) => prompt_yes_no(io::BufReader::new(tty), output, question),
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+// Rules are matched against absolute paths, so that the directories above a
+// relative path are found too.
+fn operand_path(arg: &OsStr, cwd: &Path) -> PathBuf {
+    PathBuf::from(normalize_path(cwd.join(arg).as_os_str()))
+}
+
 fn filter_arguments(
     args: impl Iterator<Item = OsString>,
-    protected_paths: &[PathBuf],
+    cwd: &Path,
+    rules: &[Rule],
+    audit_log: &AuditLog,
+    unlocks: &Unlocks,
 ) -> Vec<OsString> {
     let mut filtered_args = Vec::new();
+    let mut options_ended = false;
     for arg in args {
-        if protected_paths.contains(&PathBuf::from(normalize_path(&arg))) {
-            println!("safe-rm: Skipping {}.", arg.to_string_lossy());
-        } else {
+        // Options are passed on as they are.
+        if !options::is_operand(&arg, options_ended) {
+            options_ended |= arg == "--";
+            filtered_args.push(arg);
+            continue;
+        }
+        let path = operand_path(&arg, cwd);
+        let rule = match protecting_rule(&path, rules) {
+            Some(rule) => rule,
+            None => {
+                filtered_args.push(arg);
+                continue;
+            }
+        };
+        if rule.action != Action::Warn {
+            // The token must be good enough for everything that would go.
+            let root_only = root_only_rule(&path, rules);
+            let to_unlock = Rule {
+                system: rule.system || root_only.is_some(),
+                enforced: rule.enforced || root_only.is_some_and(|rule| rule.enforced),
+                ..rule.clone()
+            };
+            if let Some(token) = unlocks.redeem(&path, &to_unlock, unlock::now()) {
+                println!(
+                    "safe-rm: Removing unlocked path {}{}.",
+                    arg.to_string_lossy(),
+                    rule.details()
+                );
+                let unlocked = Rule {
+                    reason: Some(format!("unlocked: {}", token.reason)),
+                    ..rule
+                };
+                audit_log.record("unlocked", &arg, &unlocked);
+                filtered_args.push(arg);
+                continue;
+            }
+        }
+        let keep = match rule.action {
+            Action::Warn => {
+                println!(
+                    "safe-rm: Warning: removing protected path {}{}.",
+                    arg.to_string_lossy(),
+                    rule.details()
+                );
+                audit_log.record("warned", &arg, &rule);
+                true
+            }
+            Action::Confirm => {
+                let confirmed = confirm(&format!(
+                    "safe-rm: Remove protected path {}{}?",
+                    arg.to_string_lossy(),
+                    rule.details()
+                ));
+                let event = if confirmed { "confirmed" } else { "declined" };
+                audit_log.record(event, &arg, &rule);
+                confirmed
+            }
+            Action::Block => {
+                audit_log.record("skipped", &arg, &rule);
+                false
+            }
+        };
+        if keep {
             filtered_args.push(arg);
+        } else {
+            println!(
+                "safe-rm: Skipping {}{}.",
+                arg.to_string_lossy(),
+                rule.details()
+            );
         }
     }
     filtered_args
 }
 
-fn read_config_files(globals: &[&str], locals: &[&str]) -> Vec<PathBuf> {
+fn explain_argument(arg: &OsStr, cwd: &Path, rules: &[Rule]) -> String {
+    match protecting_rule(&operand_path(arg, cwd), rules) {
+        Some(rule) => format!(
+            "{}: {}{}",
+            arg.to_string_lossy(),
+            rule.action,
+            rule.details()
+        ),
+        None => format!("{}: not protected", arg.to_string_lossy()),
+    }
+}
+
+fn explain(
+    config: &Config,
+    args: impl Iterator<Item = OsString>,
+    globals: &[&str],
+    locals: &[&str],
+) -> i32 {
+    let protected_paths = protection_rules(config, globals, locals);
+    let cwd = std::env::current_dir().unwrap_or_default();
+    for arg in args {
+        println!("{}", explain_argument(&arg, &cwd, &protected_paths));
+    }
+    0
+}
+
+// Under sudo, $HOME may belong to either user so both users' home
+// directories are used as well.
+fn home_dirs() -> Vec<PathBuf> {
+    let mut home_dirs: Vec<PathBuf> = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    let users = identity::current_user()
+        .into_iter()
+        .chain(identity::invoking_user());
+    for user in users {
+        if !home_dirs.contains(&user.home) {
+            home_dirs.push(user.home);
+        }
+    }
+    home_dirs
+}
+
+// Somebody else being able to change a user's configuration file could hide
+// what the user meant to protect. It must belong to the owner of the home
+// directory, or to root, and only they may write to it.
+fn check_user_config(config_path: &Path, home_dir: &Path) -> Result<(), String> {
+    let metadata = match fs::metadata(config_path) {
+        Ok(metadata) => metadata,
+        // Missing files are handled by read_config().
+        Err(_) => return Ok(()),
+    };
+    let owner = fs::metadata(home_dir)
+        .map(|metadata| metadata.uid())
+        .unwrap_or_else(|_| identity::effective_uid());
+    if metadata.uid() != owner && metadata.uid() != 0 {
+        return Err(format!(
+            "{}: it is owned by uid {} instead of {}",
+            config_path.display(),
+            metadata.uid(),
+            owner
+        ));
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return Err(format!(
+            "{}: it is writable by other users",
+            config_path.display()
+        ));
+    }
+    Ok(())
+}
+
+// Drops the rules from users which would weaken an enforced rule, by covering
+// the same path with a less severe action, and warns about them.
+fn enforce(rules: Vec<Rule>) -> Vec<Rule> {
+    let enforced: Vec<(PathBuf, Action)> = rules
+        .iter()
+        .filter(|rule| rule.system && rule.enforced)
+        .map(|rule| (rule.path.clone(), rule.action))
+        .collect();
+    rules
+        .into_iter()
+        .filter(|rule| {
+            let weakens = !rule.system
+                && enforced
+                    .iter()
+                    .any(|(path, action)| *path == rule.path && rule.action < *action);
+            if weakens {
+                println!(
+                    "safe-rm: Warning: ignoring a rule which would weaken the enforced protection of {}.",
+                    rule.path.display()
+                );
+            }
+            !weakens
+        })
+        .collect()
+}
+
+fn read_config_files(globals: &[&str], locals: &[&str]) -> Vec<Rule> {
     let mut protected_paths = Vec::new();
 
     for config_file in globals {
-        if let Some(paths) = read_config(config_file) {
-            protected_paths.extend(paths.into_iter());
+        if let Some(rules) = read_config(config_file) {
+            protected_paths.extend(rules.into_iter().map(|rule| Rule {
+                system: true,
+                ..rule
+            }));
         }
     }
-    if let Ok(value) = std::env::var("HOME") {
-        let home_dir = Path::new(&value);
+    for home_dir in home_dirs() {
         for config_file in locals {
-            if let Some(paths) = read_config(&home_dir.join(Path::new(config_file))) {
-                protected_paths.extend(paths.into_iter());
+            let config_path = home_dir.join(Path::new(config_file));
+            if let Err(e) = check_user_config(&config_path, &home_dir) {
+                println!("safe-rm: Ignoring configuration file {}.", e);
+                continue;
+            }
+            if let Some(rules) = read_config(config_path) {
+                protected_paths.extend(rules);
             }
         }
     }
 
     if protected_paths.is_empty() {
         for path in DEFAULT_PATHS {
-            protected_paths.push(PathBuf::from(path));
+            protected_paths.push(Rule {
+                system: true,
+                ..Rule::new(PathBuf::from(path))
+            });
         }
     }
     protected_paths.sort();
@@ -228,6 +834,76 @@ fn read_config_files(globals: &[&str], locals: &[&str]) -> Vec<PathBuf> {
     protected_paths
 }
 
+// Combines the rules from config files with those generated at runtime.
+fn protection_rules(config: &Config, globals: &[&str], locals: &[&str]) -> Vec<Rule> {
+    let mut protected_paths = read_config_files(globals, locals);
+    let mut generated = policy::policy_rules(&config.rules, unlock::now());
+    if let Some(action) = Protection::action(config.system_files) {
+        generated.extend(dynamic::system_file_rules(
+            Path::new(dynamic::PROC_DIR),
+            Path::new(dynamic::SYS_DIR),
+            action,
+        ));
+    }
+    if let Some(action) = Protection::action(config.locked_files) {
+        generated.extend(dynamic::locked_file_rules(
+            Path::new(dynamic::PROC_DIR),
+            action,
+        ));
+    }
+    if let (Some(action), Ok(cwd)) = (
+        Protection::action(config.working_directory),
+        std::env::current_dir(),
+    ) {
+        generated.push(dynamic::working_directory_rule(&cwd, action));
+    }
+    let home_dirs = home_dirs();
+    if let Some(action) = Protection::action(config.home_directories) {
+        generated.extend(dynamic::home_directory_rules(
+            &home_dirs,
+            Path::new(dynamic::PASSWD_FILE),
+            action,
+        ));
+    }
+    if let Some(action) = config.home_contents {
+        generated.extend(dynamic::home_content_rules(&home_dirs, action));
+    }
+    if let Some(action) = config.open_files {
+        generated.extend(dynamic::open_file_rules(
+            Path::new(dynamic::PROC_DIR),
+            action,
+        ));
+    }
+    if let Some(action) = config.sensitive_files {
+        if let Ok(conditions) = predicates::parse_conditions("content = sensitive") {
+            generated.push(Rule {
+                action,
+                conditions,
+                ..Rule::new(PathBuf::from("/"))
+            });
+        }
+    }
+    if let (Some(settings), Some(user)) = (&config.other_users, identity::invoking_user()) {
+        generated.extend(ownership::other_user_rule(settings, user.uid));
+    }
+    // Like the system configuration, only root may unlock these.
+    for rule in &mut generated {
+        rule.system = true;
+    }
+    protected_paths.extend(generated);
+
+    if let Some(value) = std::env::var_os(session::PROTECT_VAR) {
+        protected_paths.extend(session::env_rules(&value));
+    }
+    for file in session::session_files(
+        std::env::var_os(session::RUNTIME_DIR_VAR),
+        identity::invoking_user().map(|user| user.uid),
+    ) {
+        protected_paths.extend(session::session_rules(&file));
+    }
+    enforce(protected_paths)
+}
+
 // fn run(
 //     rm_binary: &str,
 //     args: impl Iterator<Item = OsString>,
@@ -259,32 +935,227 @@ fn read_config_files(globals: &[&str], locals: &[&str]) -> Vec<PathBuf> {
 //     Ok(())
 // }
 
+// Running without the administrator's settings, like the digest of the real
+// rm or the forbidden options, could be worse than not running at all.
+fn read_safe_rm_config<P: AsRef<Path>>(filename: P) -> Option<Config> {
+    parse_safe_rm_config(&filename)
+        .map_err(|e| println!("safe-rm: {}", e))
+        .ok()
+}
+
+fn parse_safe_rm_config<P: AsRef<Path>>(filename: P) -> Result<Config, String> {
+    let mut toml_content = String::new();
+    if !filename.as_ref().exists() {
+        return Ok(Config::default());
+    }
+    if let Err(error) = File::open(&filename).and_then(|mut f| f.read_to_string(&mut toml_content))
+    {
+        return Err(format!(
+            "Could not read configuration file {}: {}",
+            filename.as_ref().display(),
+            error
+        ));
+    }
+    toml::from_str(&toml_content).map_err(|error| {
+        format!(
+            "Invalid configuration file {}: {}",
+            filename.as_ref().display(),
+            error
+        )
+    })
+}
+
+// Reports problems with the configuration, returning 1 if there are any.
+fn check_config<P: AsRef<Path>>(filename: P, globals: &[&str], locals: &[&str]) -> i32 {
+    let config = match parse_safe_rm_config(&filename) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("safe-rm: {}", e);
+            return 1;
+        }
+    };
+    // Problems with the other configuration files are reported while
+    // reading them.
+    read_config_files(globals, locals);
+
+    let warnings = policy::check(&config.rules, unlock::now());
+    for warning in &warnings {
+        println!("safe-rm: Warning: {}.", warning);
+    }
+    if warnings.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+// When replace_process is set, safe-rm may exec the real rm or die from the
+// same signal, and so never returns in those cases.
 fn run_binary(
-    rm_binary: String,
+    rm_command: Vec<String>,
+    config: &Config,
     args: impl Iterator<Item = OsString>,
     globals: &[&str],
     locals: &[&str],
+    replace_process: bool,
 ) -> i32 {
-    let protected_paths = read_config_files(globals, locals);
-    let filtered_args = filter_arguments(args, &protected_paths);
+    let rm_binary = &rm_command[0];
+    if let Err(e) = realrm::verify_binary(Path::new(rm_binary), config.rm_binary_sha256.as_deref())
+    {
+        println!("safe-rm: Refusing to run {}: {}.", rm_binary, e);
+        return 1;
+    }
+
+    let dialect = config
+        .rm_dialect
+        .unwrap_or_else(|| realrm::Dialect::guess(&rm_command));
+    let original_args: Vec<OsString> = args.collect();
+    let args = match &config.flags {
+        Some(policy) => match options::apply_policy(&original_args, policy, dialect) {
+            Ok(args) => args,
+            Err(e) => {
+                println!("safe-rm: Refusing to run: {}.", e);
+                return 1;
+            }
+        },
+        None => original_args.clone(),
+    };
+    // The rewritten command line is shown in verbose mode.
+    let verbose = options::parse_flag("--verbose")
+        .is_some_and(|(flag, _)| options::has_flag(&options::parse(&args), &flag));
+    let show_command = verbose && args != original_args;
+    if let Some(reason) = heuristics::suspicious_arguments(&args, &home_dirs()) {
+        println!("safe-rm: Refusing to run: {}.", reason);
+        return 1;
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        if let Some(root) = jail::jail_root(&cwd, std::env::var_os(jail::JAIL_VAR)) {
+            if let Some(reason) = jail::escaping_operand(&args, &root, &cwd) {
+                println!("safe-rm: Refusing to run: {}.", reason);
+                return 1;
+            }
+        }
+    }
+
+    let protected_paths = protection_rules(config, globals, locals);
+    let audit_log = AuditLog::new(config.audit_log.as_deref());
+    let uid = identity::effective_uid();
+    let unlocks = Unlocks::new(
+        unlock::unlock_dir(uid, std::env::var_os(session::RUNTIME_DIR_VAR)),
+        Some(PathBuf::from(unlock::ROOT_UNLOCK_DIR)),
+        uid,
+    );
+    // Relative operands are left as they are if the working directory is gone.
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let filtered_args = filter_arguments(
+        args.into_iter(),
+        &cwd,
+        &protected_paths,
+        &audit_log,
+        &unlocks,
+    );
+    let filtered_args = match dialect.translate(filtered_args) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("safe-rm: {}.", e);
+            return 1;
+        }
+    };
+
+    if show_command {
+        let command_line: Vec<String> = rm_command
+            .iter()
+            .cloned()
+            .chain(
+                filtered_args
+                    .iter()
+                    .map(|arg| arg.to_string_lossy().into_owned()),
+            )
+            .collect();
+        println!("safe-rm: Running {}", command_line.join(" "));
+    }
+
+    let mut command = process::Command::new(rm_binary);
+    command.args(&rm_command[1..]).args(&filtered_args);
+    if replace_process && !audit_log.awaits_status() {
+        let e = child::exec(&mut command);
+        println!("safe-rm: Failed to run the {} command: {}", rm_binary, e);
+        return 1;
+    }
 
     // Run the real rm command, returning with the same error code.
-    match process::Command::new(&rm_binary)
-        .args(&filtered_args)
-        .status()
-    {
-        Ok(status) => status.code().unwrap_or(1),
+    match child::run(&mut command) {
+        Ok(status) => {
+            let code = child::exit_code(status);
+            if audit_log.awaits_status() {
+                audit_log.record_status(&code.to_string());
+            }
+            if replace_process {
+                child::reraise(status);
+            }
+            code
+        }
         Err(_) => {
-            
-            println!("safe-rm: Failed to run the {} command.", &rm_binary);
+            println!("safe-rm: Failed to run the {} command.", rm_binary);
             1
         }
     }
 }
 
+// Picks the real rm command from /etc/safe-rm.toml, the environment or the
+// system, in that order.
+fn real_rm_command(config: &Config) -> Vec<String> {
+    let search_dirs = realrm::search_dirs(std::env::var_os("PATH").as_deref());
+    if let Some(command) = &config.rm_command {
+        if let Some(program) = command.first() {
+            let mut command = command.clone();
+            if let Some(program) = realrm::resolve_program(program, &search_dirs) {
+                command[0] = program;
+            }
+            return command;
+        }
+    }
+
+    // For security reasons the real `rm` binary maybe renamed, e.g.: `/bin/rm.real`
+    // Get real `rm` binary from `/etc/safe-rm.toml`
+    // e.g.: rm_binary = "/bin/rm.real"
+    if let Some(toml_real_rm) = &config.rm_binary {
+        if !toml_real_rm.is_empty() {
+            return vec![toml_real_rm.clone()];
+        }
+    }
+
+    // Get real `rm` binary from enviroment variable `SAFE_RM_REAL_RM`
+    // e.g.: export SAFE_RM_REAL_RM="/bin/rm.real"
+    if let Some(value) = std::env::var_os(realrm::REAL_RM_VAR) {
+        let path = normalize_path(&value);
+        match realrm::check_override(
+            Path::new(&path),
+            config.real_rm_override.unwrap_or_default(),
+            &config.real_rm_override_dirs,
+            identity::is_privileged(),
+        ) {
+            Ok(()) => return vec![path.to_string_lossy().into_owned()],
+            Err(e) => println!("safe-rm: Ignoring {}: {}.", realrm::REAL_RM_VAR, e),
+        }
+    }
+
+    if let Ok(current_exe) = std::env::current_exe() {
+        if let Some(command) = realrm::discover(&search_dirs, &current_exe) {
+            return command;
+        }
+    }
+    vec![String::from(REAL_RM)]
+}
+
 fn ensure_real_rm_binary_is_callable(real_rm: &mut String) -> io::Result<()> {
-    // Make sure we're not calling ourselves recursively.
-    if fs::canonicalize(&real_rm)? == fs::canonicalize(std::env::current_exe()?)? {
+    // Make sure we're not calling ourselves recursively, including through
+    // a hard link.
+    let real_rm_metadata = fs::metadata(&*real_rm)?;
+    let current_exe_metadata = fs::metadata(std::env::current_exe()?)?;
+    if real_rm_metadata.dev() == current_exe_metadata.dev()
+        && real_rm_metadata.ino() == current_exe_metadata.ino()
+    {
         println!("safe-rm: Cannot find the real \"{}\" binary.", &real_rm);
         process::exit(1);
     }
@@ -305,58 +1176,51 @@ fn main() {
     //     &[USER_CONFIG, LEGACY_USER_CONFIG],
     // ));
 
-    let mut real_rm_binary: String = "".to_string();
-
-    // For security reasons the real `rm` binary maybe renamed, e.g.: `/bin/rm.real`
-    // Get real `rm` binary from `/etc/safe-rm.toml`
-    // e.g.: rm_binary = "/bin/rm.real"
-    let mut toml_content = String::new();
-    if Path::new(SAFE_RM_CONFIG).exists() {
-        match File::open(SAFE_RM_CONFIG) {
-            Ok(mut file) => {
-                file.read_to_string(&mut toml_content).unwrap();
-            },
-            Err(error) => {
-                println!("Error opening file {}: {}", SAFE_RM_CONFIG, error);
-            },
+    let program = std::env::args_os().next().unwrap_or_default();
+    if commands::invoked_as_safe_rm(&program) {
+        let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+        if args.len() == 1 && args[0] == "check-config" {
+            process::exit(check_config(
+                SAFE_RM_CONFIG,
+                &[GLOBAL_CONFIG, LOCAL_GLOBAL_CONFIG],
+                &[USER_CONFIG, LEGACY_USER_CONFIG],
+            ));
         }
-    }
-
-    if ! toml_content.is_empty() {
-        let config: Config = toml::from_str(&toml_content).unwrap();
-        let toml_real_rm = config.rm_binary.unwrap();
-        if ! toml_real_rm.is_empty() {
-            real_rm_binary = toml_real_rm;
+        if let Some(status) = commands::run(&args) {
+            process::exit(status);
         }
     }
 
-    // Get real `rm` binary from enviroment variable `SAFE_RM_REAL_RM_BINARY`
-    // e.g.: export SAFE_RM_REAL_RM="/bin/rm.real"
-    if real_rm_binary.is_empty() {
-        if let Ok(value) = std::env::var("SAFE_RM_REAL_RM") {
-            let path  = normalize_path(Path::new(&value).as_os_str());
-            real_rm_binary = path.to_str().unwrap().to_string();
-        }
-    }
+    let config = match read_safe_rm_config(SAFE_RM_CONFIG) {
+        Some(config) => config,
+        None => process::exit(1),
+    };
 
-    if real_rm_binary.is_empty() {
-        real_rm_binary = String::from(REAL_RM);
+    let mut args = std::env::args_os().skip(1).peekable();
+    if args.peek().map(|arg| arg == "--explain") == Some(true) {
+        process::exit(explain(
+            &config,
+            args.skip(1),
+            &[GLOBAL_CONFIG, LOCAL_GLOBAL_CONFIG],
+            &[USER_CONFIG, LEGACY_USER_CONFIG],
+        ));
     }
 
-    if let Err(e) = ensure_real_rm_binary_is_callable(&mut real_rm_binary) {
+    let mut real_rm_command = real_rm_command(&config);
+
+    if let Err(e) = ensure_real_rm_binary_is_callable(&mut real_rm_command[0]) {
         println!(
             "safe-rm: Cannot check that the real \"{}\" binary is callable: {}",
-            real_rm_binary,
-            e
+            real_rm_command[0], e
         );
     }
 
-    // println!("{}", real_rm_binary);
-
     process::exit(run_binary(
-        real_rm_binary,
-        std::env::args_os().skip(1),
+        real_rm_command,
+        &config,
+        args,
         &[GLOBAL_CONFIG, LOCAL_GLOBAL_CONFIG],
         &[USER_CONFIG, LEGACY_USER_CONFIG],
+        true,
     ));
 }
@@ -0,0 +1,84 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Marker files let the owner of a directory protect it without having to
+// edit a configuration file. For example, /srv/data/.safe-rm-protect with:
+//   # reason: raw experiment data
+//   # subtree: yes
+// protects /srv/data, everything inside it, and the directories above it
+// from recursive deletion.
+
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use super::{apply_annotation, parse_annotation, Rule};
+
+pub const MARKER: &str = ".safe-rm-protect";
+
+#[derive(Debug, PartialEq)]
+pub struct Marker {
+    pub rule: Rule,
+    // Whether everything inside the directory is protected too.
+    pub subtree: bool,
+}
+
+pub fn read_marker(dir: &Path) -> Option<Marker> {
+    let filename = dir.join(MARKER);
+    if !filename.is_file() {
+        return None;
+    }
+    let mut marker = Marker {
+        rule: Rule {
+            guard_ancestors: true,
+            ..Rule::new(dir.to_path_buf())
+        },
+        subtree: false,
+    };
+    // An unreadable marker still protects the directory.
+    if let Ok(f) = File::open(&filename) {
+        for line in io::BufReader::new(f).lines().map_while(Result::ok) {
+            if let Some((key, value)) = parse_annotation(&line) {
+                if !apply_annotation(&mut marker.rule, key, value, filename.display())
+                    && key == "subtree"
+                {
+                    marker.subtree = value == "yes" || value == "true";
+                }
+            }
+        }
+    }
+    if marker.rule.reason.is_none() {
+        marker.rule.reason = Some(format!("marked with {}", MARKER));
+    }
+    Some(marker)
+}
+
+// Rule for a marker protecting the given directory, or a directory above it
+// (subtree markers only).
+pub fn marker_rule(path: &Path) -> Option<Rule> {
+    // Deleting a symlink doesn't affect the directory it points to.
+    let is_dir = path.symlink_metadata().map(|m| m.is_dir()).unwrap_or(false);
+    for dir in path.ancestors() {
+        if dir == path && !is_dir {
+            continue;
+        }
+        if let Some(marker) = read_marker(dir) {
+            if dir == path || marker.subtree {
+                return Some(marker.rule.inherited_by(path));
+            }
+        }
+    }
+    None
+}
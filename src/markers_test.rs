@@ -0,0 +1,118 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn read_marker() {
+        use super::super::markers::{read_marker, Marker, MARKER};
+        use super::super::{Action, Rule};
+
+        let dir = tempdir().unwrap();
+        assert_eq!(read_marker(dir.path()), None);
+
+        // Empty marker
+        File::create(dir.path().join(MARKER)).unwrap();
+        assert_eq!(
+            read_marker(dir.path()),
+            Some(Marker {
+                rule: Rule {
+                    reason: Some("marked with .safe-rm-protect".to_string()),
+                    guard_ancestors: true,
+                    ..Rule::new(dir.path().to_path_buf())
+                },
+                subtree: false,
+            })
+        );
+
+        // Annotated marker
+        let mut f = File::create(dir.path().join(MARKER)).unwrap();
+        writeln!(f, "# action: confirm").unwrap();
+        writeln!(f, "# reason: raw experiment data").unwrap();
+        writeln!(f, "# owner: lab").unwrap();
+        writeln!(f, "# subtree: yes").unwrap();
+        assert_eq!(
+            read_marker(dir.path()),
+            Some(Marker {
+                rule: Rule {
+                    action: Action::Confirm,
+                    reason: Some("raw experiment data".to_string()),
+                    owner: Some("lab".to_string()),
+                    guard_ancestors: true,
+                    ..Rule::new(dir.path().to_path_buf())
+                },
+                subtree: true,
+            })
+        );
+
+        // A directory named like the marker doesn't count.
+        let other = tempdir().unwrap();
+        fs::create_dir(other.path().join(MARKER)).unwrap();
+        assert_eq!(read_marker(other.path()), None);
+    }
+
+    #[test]
+    fn marker_rule() {
+        use super::super::markers::{marker_rule, MARKER};
+
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let project = root.join("project");
+        let data = project.join("data");
+        let raw = data.join("raw");
+        let scratch = project.join("scratch");
+        fs::create_dir_all(&raw).unwrap();
+        fs::create_dir_all(&scratch).unwrap();
+        File::create(raw.join("sample.csv")).unwrap();
+        File::create(scratch.join("tmp.txt")).unwrap();
+        writeln!(
+            File::create(data.join(MARKER)).unwrap(),
+            "# reason: raw data"
+        )
+        .unwrap();
+
+        // The marked directory itself
+        assert_eq!(marker_rule(&data).unwrap().reason.unwrap(), "raw data");
+
+        // Parents are checked by contents_rule() instead.
+        assert!(marker_rule(&project).is_none());
+
+        // Contents aren't protected without the subtree annotation.
+        assert!(marker_rule(&raw).is_none());
+        assert!(marker_rule(&raw.join("sample.csv")).is_none());
+        assert!(marker_rule(&scratch).is_none());
+
+        writeln!(File::create(data.join(MARKER)).unwrap(), "# subtree: yes").unwrap();
+        assert!(marker_rule(&raw).is_some());
+        assert!(marker_rule(&raw.join("sample.csv")).is_some());
+        assert!(marker_rule(&scratch).is_none());
+
+        // Symlinks to marked directories can be deleted.
+        let link = root.join("link");
+        symlink(&data, &link).unwrap();
+        let other = tempdir().unwrap();
+        let other_link = other.path().canonicalize().unwrap().join("link");
+        symlink(&data, &other_link).unwrap();
+        assert!(marker_rule(&other_link).is_none());
+        assert!(marker_rule(&PathBuf::from("/nonexistent/path")).is_none());
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Understanding of rm's command-line options, following GNU rm: short
+// options can be bundled ("-rf"), long options can be abbreviated
+// ("--recur"), options may follow operands and "--" ends them.
+//
+// Administrators can forbid, strip or add options in /etc/safe-rm.toml:
+//   [flags]
+//   forbidden = ["--no-preserve-root"]
+//   stripped = ["--verbose"]
+//   added = ["--preserve-root=all", "--one-file-system"]
+//   interactive_above = 3
+
+use serde_derive::Deserialize;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+
+use super::realrm::Dialect;
+
+// Long option names, and whether they take an optional value.
+const LONG_OPTIONS: &[(&str, bool)] = &[
+    ("dir", false),
+    ("force", false),
+    ("help", false),
+    ("interactive", true),
+    ("no-preserve-root", false),
+    ("one-file-system", false),
+    ("preserve-root", true),
+    ("recursive", false),
+    ("verbose", false),
+    ("version", false),
+];
+
+const SHORT_OPTIONS: &[(char, &str, Option<&str>)] = &[
+    ('d', "dir", None),
+    ('f', "force", None),
+    ('i', "interactive", Some("always")),
+    ('I', "interactive", Some("once")),
+    ('r', "recursive", None),
+    ('R', "recursive", None),
+    ('v', "verbose", None),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flag {
+    pub name: &'static str,
+    pub value: Option<String>,
+}
+
+impl Flag {
+    fn new(name: &'static str, value: Option<&str>) -> Flag {
+        let value = match (name, value) {
+            // These are all spellings of the same thing.
+            ("interactive", None | Some("yes")) => Some("always"),
+            ("interactive", Some("no" | "none")) => Some("never"),
+            (_, value) => value,
+        };
+        Flag {
+            name,
+            value: value.map(String::from),
+        }
+    }
+
+    // Flags without a value in a policy match any value.
+    fn matches(&self, pattern: &Flag) -> bool {
+        self.name == pattern.name && (pattern.value.is_none() || self.value == pattern.value)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    // The original text is kept for passing it on unchanged.
+    Flag(Flag, OsString),
+    Operand(OsString),
+    EndOfOptions,
+    // Left for the real rm to complain about.
+    Unknown(OsString),
+}
+
+impl Arg {
+    pub fn text(&self) -> OsString {
+        match self {
+            Arg::Flag(_, text) | Arg::Operand(text) | Arg::Unknown(text) => text.clone(),
+            Arg::EndOfOptions => OsString::from("--"),
+        }
+    }
+}
+
+fn long_option(text: &str) -> Option<Flag> {
+    let (name, value) = match text.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (text, None),
+    };
+    let matches: Vec<&(&str, bool)> = match LONG_OPTIONS.iter().find(|(long, _)| *long == name) {
+        Some(exact) => vec![exact],
+        None => LONG_OPTIONS
+            .iter()
+            .filter(|(long, _)| long.starts_with(name))
+            .collect(),
+    };
+    match matches[..] {
+        [(long, takes_value)] if *takes_value || value.is_none() => Some(Flag::new(long, value)),
+        _ => None,
+    }
+}
+
+fn short_option(option: char) -> Option<Flag> {
+    SHORT_OPTIONS
+        .iter()
+        .find(|(short, _, _)| *short == option)
+        .map(|(_, name, value)| Flag::new(name, *value))
+}
+
+// Whether rm treats the argument as a path rather than as an option.
+pub fn is_operand(arg: &OsStr, options_ended: bool) -> bool {
+    let bytes = arg.as_bytes();
+    options_ended || bytes == b"-" || !bytes.starts_with(b"-")
+}
+
+pub fn parse(args: &[OsString]) -> Vec<Arg> {
+    let mut parsed = Vec::new();
+    let mut options_ended = false;
+    for arg in args {
+        let bytes = arg.as_bytes();
+        if is_operand(arg, options_ended) {
+            parsed.push(Arg::Operand(arg.clone()));
+        } else if bytes == b"--" {
+            options_ended = true;
+            parsed.push(Arg::EndOfOptions);
+        } else if let Some(long) = arg.to_str().and_then(|arg| arg.strip_prefix("--")) {
+            parsed.push(match long_option(long) {
+                Some(flag) => Arg::Flag(flag, arg.clone()),
+                None => Arg::Unknown(arg.clone()),
+            });
+        } else {
+            // Bundled short options are split up so that they can be
+            // handled one by one.
+            let shorts: Option<Vec<Flag>> = arg
+                .to_str()
+                .map(|arg| arg[1..].chars().map(short_option).collect())
+                .unwrap_or(None);
+            match shorts {
+                Some(flags) if flags.len() == 1 => {
+                    parsed.push(Arg::Flag(flags[0].clone(), arg.clone()))
+                }
+                Some(flags) => {
+                    let options = arg.to_string_lossy()[1..].to_string();
+                    parsed.extend(
+                        flags
+                            .into_iter()
+                            .zip(options.chars())
+                            .map(|(flag, c)| Arg::Flag(flag, OsString::from(format!("-{}", c)))),
+                    )
+                }
+                None => parsed.push(Arg::Unknown(arg.clone())),
+            }
+        }
+    }
+    parsed
+}
+
+pub fn has_flag(args: &[Arg], pattern: &Flag) -> bool {
+    args.iter()
+        .any(|arg| matches!(arg, Arg::Flag(flag, _) if flag.matches(pattern)))
+}
+
+// A flag as written in the policy, e.g. "-I" or "--preserve-root=all".
+pub fn parse_flag(text: &str) -> Option<(Flag, OsString)> {
+    match parse(&[OsString::from(text)])[..] {
+        [Arg::Flag(ref flag, ref text)] => Some((flag.clone(), text.clone())),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlagPolicy {
+    // Invocations with any of these are refused.
+    #[serde(default)]
+    pub forbidden: Vec<String>,
+    // These are removed from the command line.
+    #[serde(default)]
+    pub stripped: Vec<String>,
+    // These are added unless they are already there.
+    #[serde(default)]
+    pub added: Vec<String>,
+    // Recursive deletions of more than this many operands prompt once.
+    pub interactive_above: Option<usize>,
+}
+
+fn policy_flags(texts: &[String]) -> Result<Vec<(Flag, OsString)>, String> {
+    texts
+        .iter()
+        .map(|text| {
+            parse_flag(text)
+                .ok_or_else(|| format!("unknown rm option \"{}\" in the flag policy", text))
+        })
+        .collect()
+}
+
+// Returns the rewritten command line, or why it's refused. Added flags must be
+// supported by the backend, except that -i stands in for a missing -I.
+pub fn apply_policy(
+    args: &[OsString],
+    policy: &FlagPolicy,
+    dialect: Dialect,
+) -> Result<Vec<OsString>, String> {
+    let mut parsed = parse(args);
+
+    for (flag, text) in policy_flags(&policy.forbidden)? {
+        if has_flag(&parsed, &flag) {
+            return Err(format!("{} is forbidden", text.to_string_lossy()));
+        }
+    }
+    for (flag, _) in policy_flags(&policy.stripped)? {
+        parsed.retain(|arg| !matches!(arg, Arg::Flag(f, _) if f.matches(&flag)));
+    }
+
+    let mut added = policy_flags(&policy.added)?;
+    for (_, text) in &added {
+        dialect
+            .translate(vec![text.clone()])
+            .map_err(|e| format!("{}, which the flag policy requires", e))?;
+    }
+    if let Some(limit) = policy.interactive_above {
+        let operands = parsed
+            .iter()
+            .filter(|arg| matches!(arg, Arg::Operand(_)))
+            .count();
+        if has_flag(&parsed, &Flag::new("recursive", None)) && operands > limit {
+            let prompt = if dialect.supports("-I") { "-I" } else { "-i" };
+            added.extend(parse_flag(prompt));
+        }
+    }
+    // Added flags go after the existing ones so that they take precedence,
+    // e.g. -I over an earlier -f.
+    let mut position = parsed
+        .iter()
+        .rposition(|arg| matches!(arg, Arg::Flag(..) | Arg::Unknown(_)))
+        .map_or(0, |last| last + 1);
+    if let Some(end) = parsed.iter().position(|arg| *arg == Arg::EndOfOptions) {
+        position = position.min(end);
+    }
+    for (flag, text) in added {
+        if !parsed
+            .iter()
+            .any(|arg| matches!(arg, Arg::Flag(f, _) if *f == flag))
+        {
+            parsed.insert(position, Arg::Flag(flag, text));
+            position += 1;
+        }
+    }
+
+    Ok(parsed.iter().map(Arg::text).collect())
+}
@@ -0,0 +1,171 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::args;
+    use std::ffi::OsString;
+
+    #[test]
+    fn parse() {
+        use super::super::options::{parse, parse_flag, Arg, Flag};
+
+        let flag = |name: &'static str, value: Option<&str>, text: &str| {
+            Arg::Flag(
+                Flag {
+                    name,
+                    value: value.map(String::from),
+                },
+                OsString::from(text),
+            )
+        };
+        assert_eq!(
+            parse(&args(&["-rf", "dir", "--verb", "--", "-v"])),
+            vec![
+                flag("recursive", None, "-r"),
+                flag("force", None, "-f"),
+                Arg::Operand(OsString::from("dir")),
+                flag("verbose", None, "--verb"),
+                Arg::EndOfOptions,
+                Arg::Operand(OsString::from("-v")),
+            ]
+        );
+        assert_eq!(
+            parse(&args(&["-I", "--interactive", "--interactive=no", "-"])),
+            vec![
+                flag("interactive", Some("once"), "-I"),
+                flag("interactive", Some("always"), "--interactive"),
+                flag("interactive", Some("never"), "--interactive=no"),
+                Arg::Operand(OsString::from("-")),
+            ]
+        );
+        assert_eq!(
+            parse(&args(&["--preserve-root=all", "--no-pres"])),
+            vec![
+                flag("preserve-root", Some("all"), "--preserve-root=all"),
+                flag("no-preserve-root", None, "--no-pres"),
+            ]
+        );
+
+        // Unknown, ambiguous or malformed options
+        for unknown in ["-rz", "--v", "--nope", "--force=yes", "--ver"] {
+            assert_eq!(
+                parse(&args(&[unknown])),
+                vec![Arg::Unknown(OsString::from(unknown))]
+            );
+        }
+
+        assert!(parse_flag("-I").is_some());
+        assert!(parse_flag("-rf").is_none());
+        assert!(parse_flag("file").is_none());
+    }
+
+    #[test]
+    fn apply_policy() {
+        use super::super::options::{apply_policy, FlagPolicy};
+        use super::super::realrm::Dialect;
+
+        let policy: FlagPolicy = toml::from_str(
+            r#"
+            forbidden = ["--no-preserve-root"]
+            stripped = ["-v"]
+            added = ["--preserve-root=all", "--one-file-system"]
+            interactive_above = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            apply_policy(&args(&["-r", "--no-preserve", "/"]), &policy, Dialect::Gnu).unwrap_err(),
+            "--no-preserve-root is forbidden"
+        );
+        assert_eq!(
+            apply_policy(&args(&["-rvf", "a", "b"]), &policy, Dialect::Gnu).unwrap(),
+            args(&[
+                "-r",
+                "-f",
+                "--preserve-root=all",
+                "--one-file-system",
+                "a",
+                "b"
+            ])
+        );
+        // -I is added after -f so that it takes precedence.
+        assert_eq!(
+            apply_policy(&args(&["-rf", "a", "b", "c"]), &policy, Dialect::Gnu).unwrap(),
+            args(&[
+                "-r",
+                "-f",
+                "--preserve-root=all",
+                "--one-file-system",
+                "-I",
+                "a",
+                "b",
+                "c"
+            ])
+        );
+        // Not recursive
+        assert_eq!(
+            apply_policy(&args(&["--", "a", "b", "-c"]), &policy, Dialect::Gnu).unwrap(),
+            args(&[
+                "--preserve-root=all",
+                "--one-file-system",
+                "--",
+                "a",
+                "b",
+                "-c"
+            ])
+        );
+        // Flags already present aren't added twice.
+        assert_eq!(
+            apply_policy(&args(&["--one-file-system", "a"]), &policy, Dialect::Gnu).unwrap(),
+            args(&["--one-file-system", "--preserve-root=all", "a"])
+        );
+
+        // Added flags must be supported by the backend.
+        assert_eq!(
+            apply_policy(&args(&["-rf", "a"]), &policy, Dialect::Busybox).unwrap_err(),
+            "BusyBox rm does not support --preserve-root=all, which the flag policy requires"
+        );
+        let policy: FlagPolicy = toml::from_str(
+            r#"
+            added = ["--one-file-system"]
+            interactive_above = 2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            apply_policy(&args(&["-rf", "a", "b", "c"]), &policy, Dialect::Bsd).unwrap(),
+            args(&["-r", "-f", "--one-file-system", "-I", "a", "b", "c"])
+        );
+        assert!(apply_policy(&args(&["-rf", "a", "b", "c"]), &policy, Dialect::Busybox).is_err());
+        // -i stands in for a missing -I.
+        let policy: FlagPolicy = toml::from_str("interactive_above = 2").unwrap();
+        assert_eq!(
+            apply_policy(&args(&["-rf", "a", "b", "c"]), &policy, Dialect::Busybox).unwrap(),
+            args(&["-r", "-f", "-i", "a", "b", "c"])
+        );
+
+        let policy: FlagPolicy = toml::from_str(r#"added = ["--colour"]"#).unwrap();
+        assert_eq!(
+            apply_policy(&args(&["a"]), &policy, Dialect::Gnu).unwrap_err(),
+            "unknown rm option \"--colour\" in the flag policy"
+        );
+        assert_eq!(
+            apply_policy(&args(&["-rf", "a"]), &FlagPolicy::default(), Dialect::Gnu).unwrap(),
+            args(&["-r", "-f", "a"])
+        );
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// When root deletes files on behalf of another user, typically through sudo,
+// files belonging to anybody else can be protected. For example, in
+// /etc/safe-rm.toml:
+//   [other_users]
+//   action = "confirm"
+//   allowed_uids = [33]
+//   allowed_paths = ["/srv/scratch"]
+
+use serde_derive::Deserialize;
+use std::path::PathBuf;
+
+use super::predicates::{self, Predicate};
+use super::{Action, Rule};
+
+// System accounts have uids below this, except for "nobody".
+const FIRST_REGULAR_UID: u32 = 1000;
+const NOBODY_UID: u32 = 65534;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtherUsers {
+    #[serde(default = "default_action")]
+    pub action: Action,
+    // Whether files owned by system accounts may be deleted.
+    #[serde(default = "default_allow_system_users")]
+    pub allow_system_users: bool,
+    #[serde(default)]
+    pub allowed_uids: Vec<u32>,
+    // Directories inside which anybody's files may be deleted.
+    #[serde(default)]
+    pub allowed_paths: Vec<PathBuf>,
+}
+
+fn default_action() -> Action {
+    Action::Block
+}
+
+fn default_allow_system_users() -> bool {
+    true
+}
+
+// Protects everything not owned by the invoking user, or by one of the
+// allowed users.
+pub fn other_user_rule(settings: &OtherUsers, invoking_uid: u32) -> Option<Rule> {
+    let mut conditions = vec![format!("uid != {}", invoking_uid)];
+    conditions.extend(
+        settings
+            .allowed_uids
+            .iter()
+            .map(|uid| format!("uid != {}", uid)),
+    );
+    if settings.allow_system_users {
+        conditions.push(format!("uid >= {}", FIRST_REGULAR_UID));
+        conditions.push(format!("uid != {}", NOBODY_UID));
+    }
+    let conditions: Result<Vec<Vec<Predicate>>, String> = conditions
+        .iter()
+        .map(|condition| predicates::parse_conditions(condition))
+        .collect();
+    Some(Rule {
+        action: settings.action,
+        reason: Some(format!("not owned by uid {}", invoking_uid)),
+        conditions: conditions.ok()?.concat(),
+        // Arguments get compared once their symlinks are resolved.
+        exceptions: settings
+            .allowed_paths
+            .iter()
+            .map(|path| path.canonicalize().unwrap_or_else(|_| path.clone()))
+            .collect(),
+        ..Rule::new(PathBuf::from("/"))
+    })
+}
@@ -0,0 +1,78 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::os::unix::fs::chown;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn other_user_rule() {
+        use super::super::ownership::{other_user_rule, OtherUsers};
+        use super::super::{protecting_rule, Action};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let owned_by = |name: &str, uid: u32| {
+            let path = root.join(name);
+            File::create(&path).unwrap();
+            chown(&path, Some(uid), None).map(|_| path)
+        };
+        let Ok(mine) = owned_by("mine", 1000) else {
+            return; // Changing owners requires root.
+        };
+        let theirs = owned_by("theirs", 1001).unwrap();
+        let system = owned_by("system", 33).unwrap();
+        let nobody = owned_by("nobody", 65534).unwrap();
+        let scratch = root.join("scratch");
+        fs::create_dir(&scratch).unwrap();
+        let shared = scratch.join("shared");
+        File::create(&shared).unwrap();
+        chown(&shared, Some(1001), None).unwrap();
+        chown(&root, Some(1000), None).unwrap();
+        chown(&scratch, Some(1000), None).unwrap();
+
+        let settings: OtherUsers = toml::from_str("action = \"confirm\"").unwrap();
+        assert_eq!(settings.action, Action::Confirm);
+        let rules = vec![other_user_rule(&settings, 1000).unwrap()];
+        assert!(protecting_rule(&mine, &rules).is_none());
+        let rule = protecting_rule(&theirs, &rules).unwrap();
+        assert_eq!(rule.action, Action::Confirm);
+        assert_eq!(rule.reason.unwrap(), "not owned by uid 1000");
+        assert!(protecting_rule(&system, &rules).is_none());
+        assert!(protecting_rule(&nobody, &rules).is_none());
+        // Directories containing other users' files
+        assert!(protecting_rule(&scratch, &rules).is_some());
+        assert_eq!(protecting_rule(&root, &rules).unwrap().path, root);
+
+        let settings: OtherUsers = toml::from_str(&format!(
+            "allow_system_users = false\nallowed_uids = [65534]\nallowed_paths = [{:?}]",
+            scratch
+        ))
+        .unwrap();
+        assert_eq!(settings.allowed_paths, vec![PathBuf::from(&scratch)]);
+        let rules = vec![other_user_rule(&settings, 1000).unwrap()];
+        assert_eq!(
+            protecting_rule(&theirs, &rules).unwrap().action,
+            Action::Block
+        );
+        assert!(protecting_rule(&system, &rules).is_some());
+        assert!(protecting_rule(&nobody, &rules).is_none());
+        assert!(protecting_rule(&shared, &rules).is_none());
+        assert!(protecting_rule(&scratch, &rules).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Rules listed in /etc/safe-rm.toml, which unlike those in the line-based
+// configuration files can be limited to a window of time:
+//   [[rules]]
+//   path = "/srv/release-2026-10"
+//   reason = "rollback window"
+//   expires = 2026-11-01
+// Timestamps without an offset are in UTC. Rules outside of their window are
+// ignored, which "safe-rm check-config" warns about once they have expired.
+
+use serde_derive::Deserialize;
+use std::path::Path;
+use toml::value::Datetime;
+
+use super::{parse_line, Action, Rule, SAFE_RM_CONFIG};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    // A path or glob, like the lines of the other configuration files.
+    pub path: String,
+    pub action: Option<Action>,
+    pub reason: Option<String>,
+    pub owner: Option<String>,
+    pub not_before: Option<Datetime>,
+    pub expires: Option<Datetime>,
+    // Whether rules from users may not weaken this one.
+    #[serde(default)]
+    pub enforced: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Validity {
+    NotYet,
+    Active,
+    Expired,
+}
+
+impl PolicyRule {
+    // Rules with invalid timestamps stay active, to be on the safe side.
+    pub fn validity(&self, now: u64) -> Validity {
+        let not_before = self.not_before.as_ref().and_then(|t| timestamp(t).ok());
+        let expires = self.expires.as_ref().and_then(|t| timestamp(t).ok());
+        if expires.is_some_and(|expires| expires <= now) {
+            Validity::Expired
+        } else if not_before.is_some_and(|not_before| not_before > now) {
+            Validity::NotYet
+        } else {
+            Validity::Active
+        }
+    }
+
+    fn rules(&self) -> Vec<Rule> {
+        parse_line(Path::new(SAFE_RM_CONFIG).display(), Ok(self.path.clone()))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|path| Rule {
+                action: self.action.unwrap_or(Action::Block),
+                reason: self.reason.clone(),
+                owner: self.owner.clone(),
+                enforced: self.enforced,
+                ..Rule::new(path)
+            })
+            .collect()
+    }
+}
+
+// The rules which apply at the given time.
+pub fn policy_rules(policy: &[PolicyRule], now: u64) -> Vec<Rule> {
+    policy
+        .iter()
+        .filter(|rule| rule.validity(now) == Validity::Active)
+        .flat_map(PolicyRule::rules)
+        .collect()
+}
+
+// Problems worth telling an administrator about.
+pub fn check(policy: &[PolicyRule], now: u64) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for rule in policy {
+        for (name, datetime) in &[("not_before", &rule.not_before), ("expires", &rule.expires)] {
+            if let Some(Err(e)) = datetime.as_ref().map(timestamp) {
+                warnings.push(format!(
+                    "The rule for {} has an invalid {} timestamp: {}",
+                    rule.path, name, e
+                ));
+            }
+        }
+        let window = (
+            rule.not_before.as_ref().and_then(|t| timestamp(t).ok()),
+            rule.expires.as_ref().and_then(|t| timestamp(t).ok()),
+        );
+        if let (Some(not_before), Some(expires)) = window {
+            if not_before >= expires {
+                warnings.push(format!(
+                    "The rule for {} never applies since it expires before it starts",
+                    rule.path
+                ));
+                continue;
+            }
+        }
+        if let (Validity::Expired, Some(expires)) = (rule.validity(now), &rule.expires) {
+            warnings.push(format!(
+                "The rule for {} expired on {} and is ignored",
+                rule.path, expires
+            ));
+        }
+    }
+    warnings
+}
+
+// Seconds since the epoch for TOML dates and date-times like 2026-11-01,
+// 2026-11-01T12:00:00Z or 2026-11-01 12:00:00.5+02:00.
+pub fn timestamp(datetime: &Datetime) -> Result<u64, String> {
+    let text = datetime.to_string();
+    let number = |s: &str| -> Result<i64, String> {
+        s.parse()
+            .map_err(|_| "only dates and date-times are supported".to_string())
+    };
+    if text.len() < 10 || text.as_bytes()[4] != b'-' {
+        return Err("only dates and date-times are supported".to_string());
+    }
+    let (year, month, day) = (
+        number(&text[0..4])?,
+        number(&text[5..7])?,
+        number(&text[8..10])?,
+    );
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+
+    let time = &text[10..];
+    if !time.is_empty() {
+        // The separator, then HH:MM:SS with optional fractional seconds.
+        let (clock, offset) = time[1..]
+            .find(['Z', 'z', '+', '-'])
+            .map(|i| time[1..].split_at(i))
+            .unwrap_or((&time[1..], ""));
+        let clock = clock.split('.').next().unwrap_or_default();
+        let parts: Vec<&str> = clock.split(':').collect();
+        if parts.len() != 3 {
+            return Err("invalid time of day".to_string());
+        }
+        seconds += number(parts[0])? * 3600 + number(parts[1])? * 60 + number(parts[2])?;
+        if offset.len() == 6 {
+            let sign = if offset.starts_with('-') { 1 } else { -1 };
+            seconds += sign * (number(&offset[1..3])? * 3600 + number(&offset[4..6])? * 60);
+        }
+    }
+    if seconds < 0 {
+        return Err("dates before 1970 are not supported".to_string());
+    }
+    Ok(seconds as u64)
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,144 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+    use toml::value::Datetime;
+
+    use super::super::policy::{PolicyRule, Validity};
+
+    fn datetime(text: &str) -> Datetime {
+        text.parse().unwrap()
+    }
+
+    fn rule(path: &str, not_before: Option<&str>, expires: Option<&str>) -> PolicyRule {
+        PolicyRule {
+            path: path.to_string(),
+            action: None,
+            reason: None,
+            owner: None,
+            not_before: not_before.map(datetime),
+            expires: expires.map(datetime),
+            enforced: false,
+        }
+    }
+
+    #[test]
+    fn timestamp() {
+        use super::super::policy::timestamp;
+
+        assert_eq!(timestamp(&datetime("1970-01-01")), Ok(0));
+        assert_eq!(timestamp(&datetime("2026-11-01")), Ok(1_793_491_200));
+        assert_eq!(
+            timestamp(&datetime("2000-02-29T12:30:15Z")),
+            Ok(951_827_415)
+        );
+        assert_eq!(
+            timestamp(&datetime("2026-11-01T00:00:00")),
+            Ok(1_793_491_200)
+        );
+        assert_eq!(
+            timestamp(&datetime("2026-11-01 02:00:00.75+02:00")),
+            Ok(1_793_491_200)
+        );
+        assert_eq!(
+            timestamp(&datetime("2026-10-31T19:00:00-05:00")),
+            Ok(1_793_491_200)
+        );
+        assert!(timestamp(&datetime("12:00:00")).is_err());
+        assert!(timestamp(&datetime("1969-12-31")).is_err());
+    }
+
+    #[test]
+    fn validity() {
+        let now = 1_793_491_200; // 2026-11-01
+        let window = |not_before, expires| rule("/srv", not_before, expires).validity(now);
+
+        assert_eq!(window(None, None), Validity::Active);
+        assert_eq!(window(None, Some("2026-11-02")), Validity::Active);
+        assert_eq!(window(None, Some("2026-11-01")), Validity::Expired);
+        assert_eq!(window(Some("2026-11-01"), None), Validity::Active);
+        assert_eq!(window(Some("2026-11-02"), None), Validity::NotYet);
+        assert_eq!(
+            window(Some("2026-10-01"), Some("2026-10-15")),
+            Validity::Expired
+        );
+        // Invalid timestamps don't limit the rule.
+        assert_eq!(window(Some("12:00:00"), Some("06:00:00")), Validity::Active);
+    }
+
+    #[test]
+    fn policy_rules() {
+        use super::super::policy::policy_rules;
+        use super::super::Action;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("release-1")).unwrap();
+        fs::create_dir(root.join("release-2")).unwrap();
+        fs::create_dir(root.join("expired")).unwrap();
+        let glob = root.join("release-*");
+        let now = 1_793_491_200;
+
+        let policy = vec![
+            PolicyRule {
+                action: Some(Action::Confirm),
+                reason: Some("rollback window".to_string()),
+                ..rule(glob.to_str().unwrap(), None, Some("2026-11-02"))
+            },
+            rule(
+                root.join("expired").to_str().unwrap(),
+                None,
+                Some("2026-10-31"),
+            ),
+            rule(root.join("*").to_str().unwrap(), Some("2026-11-02"), None),
+        ];
+        let rules = policy_rules(&policy, now);
+        let paths: Vec<PathBuf> = rules.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(paths, vec![root.join("release-1"), root.join("release-2")]);
+        assert_eq!(rules[0].action, Action::Confirm);
+        assert_eq!(rules[0].reason.as_deref(), Some("rollback window"));
+
+        assert_eq!(policy_rules(&policy, now - 86_400 * 2).len(), 3);
+        assert_eq!(policy_rules(&policy, now + 86_400 * 2).len(), 3);
+        assert!(policy_rules(&[], now).is_empty());
+    }
+
+    #[test]
+    fn check() {
+        use super::super::policy::check;
+
+        let now = 1_793_491_200;
+        assert!(check(&[rule("/srv", None, Some("2026-11-02"))], now).is_empty());
+        assert!(check(&[rule("/srv", Some("2026-11-02"), None)], now).is_empty());
+        assert_eq!(
+            check(&[rule("/srv", None, Some("2026-10-31"))], now),
+            vec!["The rule for /srv expired on 2026-10-31 and is ignored"]
+        );
+        assert_eq!(
+            check(&[rule("/srv", Some("2026-12-01"), Some("2026-11-15"))], now),
+            vec!["The rule for /srv never applies since it expires before it starts"]
+        );
+        assert_eq!(
+            check(&[rule("/srv", None, Some("12:00:00"))], now),
+            vec![
+                "The rule for /srv has an invalid expires timestamp: only dates and date-times are supported"
+            ]
+        );
+    }
+}
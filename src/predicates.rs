@@ -0,0 +1,259 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Rules can be restricted to files whose metadata matches a set of
+// conditions, for example:
+//   # when: size > 1GiB and mtime < 1h
+//   /data
+// protects the large files under /data which were modified within the last
+// hour. Unlike plain rules, these apply to everything below the given path.
+
+use std::fmt;
+use std::fs::Metadata;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::classifier::{self, Content};
+use super::identity;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Field {
+    Size,
+    // Time elapsed since the last modification.
+    Mtime,
+    Uid,
+    Type,
+    Links,
+    Content,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    Device,
+    Socket,
+    Fifo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Value {
+    Number(u64),
+    Kind(FileKind),
+    // None stands for any of the recognised kinds of sensitive files.
+    Content(Option<Content>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Predicate {
+    field: Field,
+    comparison: Comparison,
+    value: Value,
+    // As written in the configuration file, for messages.
+    text: String,
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+// Parses conditions joined by "and", e.g. "size > 1GiB and mtime < 1h".
+pub fn parse_conditions(conditions: &str) -> Result<Vec<Predicate>, String> {
+    let mut predicates = Vec::new();
+    let mut clause = Vec::new();
+    for word in conditions.split_whitespace().chain(std::iter::once("and")) {
+        if word != "and" {
+            clause.push(word);
+            continue;
+        }
+        if clause.is_empty() {
+            return Err("empty condition".to_string());
+        }
+        predicates.push(parse_predicate(&clause.join(" "))?);
+        clause.clear();
+    }
+    Ok(predicates)
+}
+
+fn parse_predicate(text: &str) -> Result<Predicate, String> {
+    let start = text
+        .find(['<', '>', '=', '!'])
+        .ok_or_else(|| format!("missing comparison in \"{}\"", text))?;
+    let end = text[start..]
+        .find(|c| !"<>=!".contains(c))
+        .map_or(text.len(), |offset| start + offset);
+    let (name, operator, value) = (text[..start].trim(), &text[start..end], text[end..].trim());
+
+    let field = match name {
+        "size" => Field::Size,
+        "mtime" => Field::Mtime,
+        "uid" => Field::Uid,
+        "type" => Field::Type,
+        "links" => Field::Links,
+        "content" => Field::Content,
+        _ => return Err(format!("unknown attribute \"{}\"", name)),
+    };
+    let comparison = match operator {
+        "<" => Comparison::Less,
+        "<=" => Comparison::LessOrEqual,
+        "=" | "==" => Comparison::Equal,
+        "!=" => Comparison::NotEqual,
+        ">=" => Comparison::GreaterOrEqual,
+        ">" => Comparison::Greater,
+        _ => return Err(format!("unknown comparison \"{}\"", operator)),
+    };
+    let value = match field {
+        Field::Size => parse_size(value).map(Value::Number),
+        Field::Mtime => parse_duration(value).map(Value::Number),
+        Field::Uid if value == "self" => Some(Value::Number(identity::effective_uid().into())),
+        Field::Uid | Field::Links => value.parse().ok().map(Value::Number),
+        Field::Type => parse_kind(value).map(Value::Kind),
+        Field::Content if value == "sensitive" => Some(Value::Content(None)),
+        Field::Content => Content::from_name(value).map(|c| Value::Content(Some(c))),
+    }
+    .ok_or_else(|| format!("invalid value \"{}\" for {}", value, name))?;
+    if matches!(field, Field::Type | Field::Content)
+        && !matches!(comparison, Comparison::Equal | Comparison::NotEqual)
+    {
+        return Err(format!(
+            "{} can only be compared with = or != in \"{}\"",
+            name, text
+        ));
+    }
+
+    Ok(Predicate {
+        field,
+        comparison,
+        value,
+        text: text.to_string(),
+    })
+}
+
+// Sizes in bytes, with optional decimal (kB, MB...) or binary (K, KiB...)
+// units.
+fn parse_size(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "kB" | "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        "K" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "T" | "TiB" => 1 << 40,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+// Durations in seconds, with an optional unit.
+pub fn parse_duration(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" | "s" => 1,
+        "m" | "min" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn parse_kind(value: &str) -> Option<FileKind> {
+    match value {
+        "file" => Some(FileKind::File),
+        "dir" => Some(FileKind::Dir),
+        "symlink" => Some(FileKind::Symlink),
+        "device" => Some(FileKind::Device),
+        "socket" => Some(FileKind::Socket),
+        "fifo" => Some(FileKind::Fifo),
+        _ => None,
+    }
+}
+
+fn file_kind(metadata: &Metadata) -> FileKind {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        FileKind::Dir
+    } else if file_type.is_symlink() {
+        FileKind::Symlink
+    } else if file_type.is_block_device() || file_type.is_char_device() {
+        FileKind::Device
+    } else if file_type.is_socket() {
+        FileKind::Socket
+    } else if file_type.is_fifo() {
+        FileKind::Fifo
+    } else {
+        FileKind::File
+    }
+}
+
+impl Predicate {
+    // The metadata should come from symlink_metadata() since deleting a
+    // symlink doesn't affect its target.
+    pub fn matches(&self, path: &Path, metadata: &Metadata) -> bool {
+        let actual = match self.field {
+            Field::Size => Value::Number(metadata.len()),
+            Field::Mtime => Value::Number(match metadata.modified() {
+                Ok(modified) => SystemTime::now()
+                    .duration_since(modified)
+                    .map_or(0, |age| age.as_secs()),
+                Err(_) => return false,
+            }),
+            Field::Uid => Value::Number(metadata.uid().into()),
+            Field::Type => Value::Kind(file_kind(metadata)),
+            Field::Links => Value::Number(metadata.nlink()),
+            Field::Content => {
+                let content = classifier::classify(path);
+                let equal = match self.value {
+                    Value::Content(None) => content.is_some(),
+                    value => value == Value::Content(content),
+                };
+                return equal == (self.comparison == Comparison::Equal);
+            }
+        };
+        match self.comparison {
+            Comparison::Less => actual < self.value,
+            Comparison::LessOrEqual => actual <= self.value,
+            Comparison::Equal => actual == self.value,
+            Comparison::NotEqual => actual != self.value,
+            Comparison::GreaterOrEqual => actual >= self.value,
+            Comparison::Greater => actual > self.value,
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Finding and checking the real rm binary that safe-rm hands the remaining
+// arguments to.
+//
+// Unless configured otherwise, the real rm is searched for in the system
+// directories and then in $PATH, skipping safe-rm itself, e.g. when it's
+// installed as /bin/rm on a usrmerge system or through a dpkg diversion.
+//
+// Before running it, safe-rm makes sure that only root could have modified
+// it, and optionally that it matches a pinned SHA-256 digest:
+//   rm_binary_sha256 = "0a1b..."
+//
+// Backends other than GNU rm can be used through a command line and dialect:
+//   rm_command = ["busybox", "rm"]
+//   rm_dialect = "busybox"
+// GNU long options are then translated to the short options they support.
+//
+// The SAFE_RM_REAL_RM environment variable can point safe-rm at a different
+// binary. Since any process can set it, administrators can restrict it in
+// /etc/safe-rm.toml:
+//   real_rm_override = "restrict"
+//   real_rm_override_dirs = ["/usr/local/libexec/safe-rm"]
+
+use serde_derive::Deserialize;
+use sha2::{Digest, Sha256};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+pub const REAL_RM_VAR: &str = "SAFE_RM_REAL_RM";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvOverride {
+    #[default]
+    Allow,
+    // Only root-owned binaries in the allowed directories.
+    Restrict,
+    Ignore,
+}
+
+// Returns why the given override can't be used, if it can't.
+pub fn check_override(
+    path: &Path,
+    mode: EnvOverride,
+    allowed_dirs: &[PathBuf],
+    privileged: bool,
+) -> Result<(), String> {
+    if privileged {
+        return Err("overrides are disabled under sudo or setuid".to_string());
+    }
+    match mode {
+        EnvOverride::Allow => Ok(()),
+        EnvOverride::Ignore => Err("overrides are disabled".to_string()),
+        EnvOverride::Restrict => {
+            let path = path
+                .canonicalize()
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            let in_allowed_dir = path.parent().is_some_and(|parent| {
+                allowed_dirs
+                    .iter()
+                    .filter_map(|dir| dir.canonicalize().ok())
+                    .any(|dir| dir == parent)
+            });
+            if !in_allowed_dir {
+                return Err(format!("{} is not in an allowed directory", path.display()));
+            }
+            let metadata = path
+                .metadata()
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            if metadata.uid() != 0 {
+                return Err(format!("{} is not owned by root", path.display()));
+            }
+            Ok(())
+        }
+    }
+}
+
+const TRUSTED_DIRS: &[&str] = &["/usr/bin", "/bin", "/usr/sbin", "/sbin"];
+
+// Names under which the real rm can be found, in order of preference within
+// a directory. Diversions come first since "rm" next to them is likely to be
+// safe-rm itself.
+const CANDIDATES: &[&[&str]] = &[
+    &["rm.real"],
+    &["rm.distrib"],
+    &["rm"],
+    &["coreutils", "rm"],
+    &["busybox", "rm"],
+];
+
+// The system directories followed by the absolute entries of $PATH.
+pub fn search_dirs(path_var: Option<&OsStr>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = TRUSTED_DIRS.iter().map(PathBuf::from).collect();
+    if let Some(path_var) = path_var {
+        dirs.extend(std::env::split_paths(path_var).filter(|dir| dir.is_absolute()));
+    }
+    dirs
+}
+
+// Returns the command line of the first rm which isn't the given executable.
+pub fn discover(dirs: &[PathBuf], current_exe: &Path) -> Option<Vec<String>> {
+    let current_exe = current_exe.metadata().ok();
+    for dir in dirs {
+        for candidate in CANDIDATES {
+            let program = dir.join(candidate[0]);
+            let metadata = match program.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+            let is_current_exe = current_exe
+                .as_ref()
+                .is_some_and(|exe| (exe.dev(), exe.ino()) == (metadata.dev(), metadata.ino()));
+            if is_current_exe {
+                continue;
+            }
+            if let Some(program) = program.to_str() {
+                let mut command = vec![program.to_string()];
+                command.extend(candidate[1..].iter().map(|arg| arg.to_string()));
+                return Some(command);
+            }
+        }
+    }
+    None
+}
+
+// Write permission for the group or for other users.
+const GROUP_OR_OTHER_WRITE: u32 = 0o022;
+const ANY_EXECUTE: u32 = 0o111;
+
+// Returns why the given binary can't be trusted, if it can't.
+pub fn verify_binary(path: &Path, sha256: Option<&str>) -> Result<(), String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let metadata = path
+        .metadata()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a regular file", path.display()));
+    }
+    if metadata.permissions().mode() & ANY_EXECUTE == 0 {
+        return Err(format!("{} is not executable", path.display()));
+    }
+    check_owner_and_mode(&path, &metadata)?;
+    for dir in path.ancestors().skip(1) {
+        let metadata = dir
+            .metadata()
+            .map_err(|e| format!("{}: {}", dir.display(), e))?;
+        check_owner_and_mode(dir, &metadata)?;
+    }
+
+    if let Some(expected) = sha256 {
+        let actual = sha256_digest(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!(
+                "{} has SHA-256 {} instead of {}",
+                path.display(),
+                actual,
+                expected.trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_owner_and_mode(path: &Path, metadata: &fs::Metadata) -> Result<(), String> {
+    if metadata.uid() != 0 {
+        return Err(format!("{} is not owned by root", path.display()));
+    }
+    if metadata.permissions().mode() & GROUP_OR_OTHER_WRITE != 0 {
+        return Err(format!(
+            "{} is writable by users other than root",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+pub fn sha256_digest(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+// The rm implementation behind the real rm command, which determines the
+// options it understands.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    Gnu,
+    Bsd,
+    Busybox,
+    Uutils,
+}
+
+// GNU long options and their short equivalents.
+const LONG_OPTIONS: &[(&str, char)] = &[
+    ("--dir", 'd'),
+    ("--force", 'f'),
+    ("--interactive", 'i'),
+    ("--one-file-system", 'x'),
+    ("--recursive", 'r'),
+    ("--verbose", 'v'),
+];
+
+impl Dialect {
+    // Multi-call binaries are recognised from their names, including through
+    // symlinks like /bin/rm on Alpine.
+    pub fn guess(command: &[String]) -> Dialect {
+        let program = command
+            .first()
+            .map(|program| fs::canonicalize(program).unwrap_or_else(|_| PathBuf::from(program)));
+        let name = program
+            .as_deref()
+            .and_then(Path::file_name)
+            .and_then(OsStr::to_str);
+        match name {
+            Some("busybox") => Dialect::Busybox,
+            Some("coreutils") if command.len() > 1 => Dialect::Uutils,
+            _ => Dialect::Gnu,
+        }
+    }
+
+    fn long_options(self) -> bool {
+        matches!(self, Dialect::Gnu | Dialect::Uutils)
+    }
+
+    fn short_options(self) -> &'static str {
+        match self {
+            Dialect::Gnu | Dialect::Uutils => "dfiIrRv",
+            Dialect::Bsd => "dfiIPrRvWx",
+            Dialect::Busybox => "firR",
+        }
+    }
+
+    // Whether the backend understands a GNU-style option, once translated.
+    pub fn supports(self, option: &str) -> bool {
+        self.translate(vec![OsString::from(option)]).is_ok()
+    }
+
+    // Converts GNU-style arguments into ones the backend understands.
+    pub fn translate(self, args: Vec<OsString>) -> Result<Vec<OsString>, String> {
+        if self.long_options() {
+            return Ok(args);
+        }
+        let mut translated = Vec::new();
+        let mut args = args.into_iter();
+        for arg in args.by_ref() {
+            if arg == "--" {
+                translated.push(arg);
+                break;
+            }
+            let text = arg.to_string_lossy();
+            if text.starts_with("--") {
+                let short = LONG_OPTIONS
+                    .iter()
+                    .find(|(long, _)| *long == text)
+                    .map(|(_, short)| *short)
+                    .filter(|short| self.short_options().contains(*short))
+                    .ok_or_else(|| format!("{} rm does not support {}", self, text))?;
+                translated.push(OsString::from(format!("-{}", short)));
+            } else if text.len() > 1 && text.starts_with('-') {
+                if let Some(short) = text[1..]
+                    .chars()
+                    .find(|short| !self.short_options().contains(*short))
+                {
+                    return Err(format!("{} rm does not support -{}", self, short));
+                }
+                translated.push(arg);
+            } else {
+                translated.push(arg);
+            }
+        }
+        translated.extend(args);
+        Ok(translated)
+    }
+}
+
+impl std::fmt::Display for Dialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Dialect::Gnu => "GNU",
+            Dialect::Bsd => "BSD",
+            Dialect::Busybox => "BusyBox",
+            Dialect::Uutils => "uutils",
+        })
+    }
+}
+
+// Finds programs given by name, like "busybox", in the given directories.
+pub fn resolve_program(program: &str, dirs: &[PathBuf]) -> Option<String> {
+    if program.contains('/') {
+        return Some(program.to_string());
+    }
+    dirs.iter()
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+        .and_then(|path| path.to_str().map(String::from))
+}
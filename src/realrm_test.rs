@@ -0,0 +1,268 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::args;
+    use std::fs::{self, File};
+    use std::os::unix::fs::chown;
+    use tempfile::tempdir;
+
+    #[test]
+    fn check_override() {
+        use super::super::realrm::{check_override, EnvOverride};
+
+        let dir = tempdir().unwrap();
+        let allowed_dir = dir.path().join("libexec");
+        fs::create_dir(&allowed_dir).unwrap();
+        let allowed = allowed_dir.join("rm");
+        File::create(&allowed).unwrap();
+        let elsewhere = dir.path().join("rm");
+        File::create(&elsewhere).unwrap();
+        let allowed_dirs = vec![allowed_dir.clone()];
+
+        assert!(check_override(&elsewhere, EnvOverride::Allow, &[], false).is_ok());
+        assert!(check_override(&elsewhere, EnvOverride::Ignore, &[], false).is_err());
+        // Privileged processes never accept overrides.
+        assert_eq!(
+            check_override(&elsewhere, EnvOverride::Allow, &[], true).unwrap_err(),
+            "overrides are disabled under sudo or setuid"
+        );
+        assert!(check_override(&allowed, EnvOverride::Restrict, &allowed_dirs, true).is_err());
+
+        assert!(check_override(&elsewhere, EnvOverride::Restrict, &allowed_dirs, false).is_err());
+        assert!(check_override(&allowed, EnvOverride::Restrict, &[], false).is_err());
+        assert!(check_override(
+            &allowed_dir.join("missing"),
+            EnvOverride::Restrict,
+            &allowed_dirs,
+            false
+        )
+        .is_err());
+        // Symlinks out of the allowed directories don't count.
+        let link = allowed_dir.join("link");
+        std::os::unix::fs::symlink(&elsewhere, &link).unwrap();
+        assert!(check_override(&link, EnvOverride::Restrict, &allowed_dirs, false).is_err());
+
+        if chown(&allowed, Some(0), None).is_err() {
+            return; // Changing owners requires root.
+        }
+        assert!(check_override(&allowed, EnvOverride::Restrict, &allowed_dirs, false).is_ok());
+        chown(&allowed, Some(1000), None).unwrap();
+        assert_eq!(
+            check_override(&allowed, EnvOverride::Restrict, &allowed_dirs, false).unwrap_err(),
+            format!(
+                "{} is not owned by root",
+                allowed.canonicalize().unwrap().display()
+            )
+        );
+    }
+
+    #[test]
+    fn verify_binary() {
+        use super::super::realrm::{sha256_digest, verify_binary};
+        use super::super::REAL_RM;
+        use std::os::unix::fs::PermissionsExt;
+        use std::path::Path;
+
+        let real_rm = Path::new(REAL_RM);
+        if verify_binary(real_rm, None).is_err() {
+            return; // Not a root-owned system rm.
+        }
+        let digest = sha256_digest(real_rm).unwrap();
+        assert_eq!(digest.len(), 64);
+        assert!(verify_binary(real_rm, Some(&digest)).is_ok());
+        assert!(verify_binary(real_rm, Some(&digest.to_uppercase())).is_ok());
+        assert!(verify_binary(real_rm, Some(&"0".repeat(64))).is_err());
+
+        assert!(verify_binary(Path::new("/nonexistent/rm"), None).is_err());
+        assert!(verify_binary(Path::new("/usr"), None)
+            .unwrap_err()
+            .ends_with("is not a regular file"));
+
+        let dir = tempdir().unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        let binary = dir.path().join("rm");
+        File::create(&binary).unwrap();
+        assert!(verify_binary(&binary, None)
+            .unwrap_err()
+            .ends_with("is not executable"));
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(verify_binary(&binary, None)
+            .unwrap_err()
+            .ends_with("is writable by users other than root"));
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        // The temporary directory lives in a world-writable directory.
+        let error = verify_binary(&binary, None).unwrap_err();
+        assert!(error.ends_with("is writable by users other than root"));
+        assert!(!error.starts_with(&binary.canonicalize().unwrap().display().to_string()));
+        if chown(&binary, Some(1000), None).is_ok() {
+            assert!(verify_binary(&binary, None)
+                .unwrap_err()
+                .ends_with("is not owned by root"));
+        }
+    }
+
+    #[test]
+    fn search_dirs() {
+        use super::super::realrm::search_dirs;
+        use std::path::PathBuf;
+
+        let path_var = std::env::join_paths(["/usr/local/bin", "bin", "/opt/bin"]).unwrap();
+        let dirs = search_dirs(Some(&path_var));
+        assert_eq!(dirs[0], PathBuf::from("/usr/bin"));
+        assert_eq!(
+            dirs[dirs.len() - 2..],
+            [PathBuf::from("/usr/local/bin"), PathBuf::from("/opt/bin")]
+        );
+        assert_eq!(search_dirs(None).len(), dirs.len() - 2);
+    }
+
+    #[test]
+    fn discover() {
+        use super::super::realrm::discover;
+        use std::path::Path;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir(&first).unwrap();
+        fs::create_dir(&second).unwrap();
+        let dirs = vec![first.clone(), second.clone()];
+        let safe_rm = root.join("safe-rm");
+        File::create(&safe_rm).unwrap();
+        let rm = |dir: &Path, name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        assert_eq!(discover(&dirs, &safe_rm), None);
+
+        // safe-rm installed as rm in the first directory
+        fs::hard_link(&safe_rm, first.join("rm")).unwrap();
+        assert_eq!(discover(&dirs, &safe_rm), None);
+        File::create(second.join("rm")).unwrap();
+        assert_eq!(discover(&dirs, &safe_rm).unwrap(), vec![rm(&second, "rm")]);
+        std::os::unix::fs::symlink(&safe_rm, second.join("rm.real")).unwrap();
+        assert_eq!(discover(&dirs, &safe_rm).unwrap(), vec![rm(&second, "rm")]);
+
+        // Diversions
+        File::create(first.join("rm.distrib")).unwrap();
+        assert_eq!(
+            discover(&dirs, &safe_rm).unwrap(),
+            vec![rm(&first, "rm.distrib")]
+        );
+
+        // Multi-call binaries
+        fs::remove_file(first.join("rm.distrib")).unwrap();
+        fs::remove_file(second.join("rm")).unwrap();
+        File::create(second.join("busybox")).unwrap();
+        assert_eq!(
+            discover(&dirs, &safe_rm).unwrap(),
+            vec![rm(&second, "busybox"), "rm".to_string()]
+        );
+    }
+
+    #[test]
+    fn dialect() {
+        use super::super::realrm::Dialect;
+
+        let command = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(Dialect::guess(&command(&["/bin/rm"])), Dialect::Gnu);
+        assert_eq!(
+            Dialect::guess(&command(&["/bin/busybox", "rm"])),
+            Dialect::Busybox
+        );
+        assert_eq!(
+            Dialect::guess(&command(&["/usr/bin/coreutils", "rm"])),
+            Dialect::Uutils
+        );
+        assert_eq!(Dialect::guess(&[]), Dialect::Gnu);
+
+        let dir = tempdir().unwrap();
+        let busybox = dir.path().join("busybox");
+        File::create(&busybox).unwrap();
+        let rm = dir.path().join("rm");
+        std::os::unix::fs::symlink(&busybox, &rm).unwrap();
+        assert_eq!(
+            Dialect::guess(&command(&[rm.to_str().unwrap()])),
+            Dialect::Busybox
+        );
+
+        let gnu_args = args(&["--recursive", "--force", "--one-file-system", "dir"]);
+        assert_eq!(Dialect::Gnu.translate(gnu_args.clone()).unwrap(), gnu_args);
+        assert_eq!(
+            Dialect::Uutils.translate(gnu_args.clone()).unwrap(),
+            gnu_args
+        );
+        assert_eq!(
+            Dialect::Busybox
+                .translate(args(&[
+                    "--recursive",
+                    "-f",
+                    "--force",
+                    "dir",
+                    "--",
+                    "--verbose"
+                ]))
+                .unwrap(),
+            args(&["-r", "-f", "-f", "dir", "--", "--verbose"])
+        );
+        assert_eq!(
+            Dialect::Bsd.translate(args(&["--verbose", "dir"])).unwrap(),
+            args(&["-v", "dir"])
+        );
+        assert_eq!(
+            Dialect::Busybox
+                .translate(args(&["--verbose", "dir"]))
+                .unwrap_err(),
+            "BusyBox rm does not support --verbose"
+        );
+        assert_eq!(
+            Dialect::Bsd
+                .translate(args(&["--one-file-system", "-rx", "dir"]))
+                .unwrap(),
+            args(&["-x", "-rx", "dir"])
+        );
+        assert!(Dialect::Bsd.translate(args(&["--preserve-root"])).is_err());
+
+        // Short options are checked too.
+        assert_eq!(
+            Dialect::Busybox
+                .translate(args(&["-rI", "dir", "-", "--", "-v"]))
+                .unwrap_err(),
+            "BusyBox rm does not support -I"
+        );
+        assert_eq!(
+            Dialect::Busybox
+                .translate(args(&["-rf", "-", "--", "-v"]))
+                .unwrap(),
+            args(&["-rf", "-", "--", "-v"])
+        );
+        assert!(Dialect::Busybox.translate(args(&["-x", "dir"])).is_err());
+    }
+
+    #[test]
+    fn resolve_program() {
+        use super::super::realrm::resolve_program;
+
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("busybox")).unwrap();
+        let dirs = vec![dir.path().join("missing"), dir.path().to_path_buf()];
+        assert_eq!(
+            resolve_program("busybox", &dirs).unwrap(),
+            dir.path().join("busybox").to_str().unwrap()
+        );
+        assert_eq!(resolve_program("coreutils", &dirs), None);
+        assert_eq!(resolve_program("./busybox", &[]).unwrap(), "./busybox");
+    }
+}
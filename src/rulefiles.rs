@@ -0,0 +1,209 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Rule files checked into a project protect paths underneath the directory
+// containing them, using the same syntax as .gitignore files:
+//   *.kdbx        a file or directory with that name, anywhere
+//   /build.log    relative to the directory of the rule file
+//   data/raw/     a directory (and everything inside it)
+//   docs/**/*.pdf any number of intermediate directories
+//   !scratch.kdbx except for this one
+// Annotations like "# reason: ..." apply to the next pattern.
+
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{apply_annotation, parse_annotation, Rule};
+
+pub const RULE_FILE: &str = ".safe-rm-rules";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug)]
+pub struct RulePattern {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    // Anchored patterns are matched against the path relative to the rule
+    // file, the others against the file name only.
+    anchored: bool,
+    pub rule: Rule,
+}
+
+impl RulePattern {
+    fn matches(&self, relative_path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            self.pattern.matches_with(relative_path, MATCH_OPTIONS)
+        } else {
+            let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+            self.pattern.matches_with(file_name, MATCH_OPTIONS)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RuleFile {
+    dir: PathBuf,
+    pub patterns: Vec<RulePattern>,
+}
+
+pub fn parse_rule_file(dir: &Path, contents: &str) -> RuleFile {
+    let filename = dir.join(RULE_FILE);
+    let mut patterns = Vec::new();
+    let mut annotations = Rule::new(PathBuf::new());
+    for line in contents.lines() {
+        if line.starts_with('#') {
+            if let Some((key, value)) = parse_annotation(line) {
+                apply_annotation(&mut annotations, key, value, filename.display());
+            }
+            continue;
+        }
+        let mut text = line.trim_end();
+        if text.is_empty() {
+            continue;
+        }
+        // A leading backslash escapes a literal "!" or "#".
+        let negated = text.starts_with('!');
+        if negated || text.starts_with("\\!") || text.starts_with("\\#") {
+            text = &text[1..];
+        }
+        let dir_only = text.ends_with('/');
+        let text = text.trim_end_matches('/');
+        let anchored = text.contains('/');
+        let text = text.trim_start_matches('/');
+        if text.is_empty() {
+            continue;
+        }
+        match Pattern::new(text) {
+            Ok(pattern) => {
+                let mut rule = annotations.clone();
+                if rule.reason.is_none() {
+                    rule.reason = Some(format!("matches \"{}\" in {}", line, filename.display()));
+                }
+                patterns.push(RulePattern {
+                    pattern,
+                    negated,
+                    dir_only,
+                    anchored,
+                    rule,
+                });
+            }
+            Err(_) => println!(
+                "safe-rm: Invalid pattern \"{}\" found in {} and ignored.",
+                line,
+                filename.display()
+            ),
+        }
+        annotations = Rule::new(PathBuf::new());
+    }
+    RuleFile {
+        dir: dir.to_path_buf(),
+        patterns,
+    }
+}
+
+// Reads and caches the rule file, if any, of each directory.
+#[derive(Default)]
+pub struct RuleFileCache {
+    files: HashMap<PathBuf, Option<RuleFile>>,
+}
+
+impl RuleFileCache {
+    fn get(&mut self, dir: &Path) -> Option<&RuleFile> {
+        self.files
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let filename = dir.join(RULE_FILE);
+                if !filename.is_file() {
+                    return None;
+                }
+                match fs::read_to_string(&filename) {
+                    Ok(contents) => Some(parse_rule_file(dir, &contents)),
+                    Err(_) => {
+                        println!("safe-rm: Could not open rule file: {}", filename.display());
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    // Returns the rule protecting the given path according to the rule files
+    // found in the directories above it.
+    pub fn matching_rule(&mut self, path: &Path, is_dir: bool) -> Option<Rule> {
+        // The last ancestor of a relative path is "", which would stand for
+        // whatever the working directory happens to be.
+        if path.is_relative() {
+            return None;
+        }
+        // Rule files from the top-most directory down, so that the last
+        // pattern to match has the highest precedence, just like git.
+        let mut dirs: Vec<&Path> = path.ancestors().skip(1).collect();
+        dirs.reverse();
+        for dir in &dirs {
+            self.get(dir);
+        }
+        let rule_files: Vec<&RuleFile> = dirs
+            .iter()
+            .filter_map(|dir| self.files.get(*dir).and_then(Option::as_ref))
+            .collect();
+        if rule_files.is_empty() {
+            return None;
+        }
+
+        // A path is protected if it, or any directory it's in, is protected.
+        // As with git, nothing inside a protected directory can be excluded.
+        let mut prefixes: Vec<&Path> = path.ancestors().collect();
+        prefixes.reverse();
+        for prefix in prefixes {
+            let prefix_is_dir = prefix != path || is_dir;
+            let mut last_match: Option<&RulePattern> = None;
+            for rule_file in &rule_files {
+                let relative_path = match prefix.strip_prefix(&rule_file.dir) {
+                    Ok(relative_path) if prefix != rule_file.dir => relative_path,
+                    _ => continue,
+                };
+                let relative_path = relative_path.to_string_lossy();
+                for pattern in &rule_file.patterns {
+                    if pattern.matches(&relative_path, prefix_is_dir) {
+                        last_match = Some(pattern);
+                    }
+                }
+            }
+            if let Some(pattern) = last_match.filter(|pattern| !pattern.negated) {
+                return Some(Rule {
+                    path: path.to_path_buf(),
+                    ..pattern.rule.clone()
+                });
+            }
+        }
+        None
+    }
+}
+
+// Rule for the given path according to the rule files above it.
+pub fn rule_file_rule(path: &Path) -> Option<Rule> {
+    let is_dir = path.symlink_metadata().map(|m| m.is_dir()).unwrap_or(false);
+    RuleFileCache::default().matching_rule(path, is_dir)
+}
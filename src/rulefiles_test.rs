@@ -0,0 +1,144 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn protected(rules: &str, path: &str, is_dir: bool) -> bool {
+        use super::super::rulefiles::{RuleFileCache, RULE_FILE};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join(RULE_FILE), rules).unwrap();
+        RuleFileCache::default()
+            .matching_rule(&root.join(path), is_dir)
+            .is_some()
+    }
+
+    #[test]
+    fn relative_paths() {
+        use super::super::rulefiles::RuleFileCache;
+
+        assert!(RuleFileCache::default()
+            .matching_rule(Path::new("-rf"), false)
+            .is_none());
+    }
+
+    #[test]
+    fn floating_patterns() {
+        assert!(protected("*.kdbx", "passwords.kdbx", false));
+        assert!(protected("*.kdbx", "a/b/passwords.kdbx", false));
+        assert!(!protected("*.kdbx", "passwords.kdbx.bak", false));
+        assert!(protected("secrets", "a/secrets", true));
+        assert!(protected("secrets", "a/secrets/key", false));
+    }
+
+    #[test]
+    fn anchored_patterns() {
+        assert!(protected("/build.log", "build.log", false));
+        assert!(!protected("/build.log", "sub/build.log", false));
+        assert!(protected("data/raw", "data/raw", true));
+        assert!(!protected("data/raw", "other/data/raw", true));
+        assert!(!protected("data/*.csv", "data/sub/x.csv", false));
+    }
+
+    #[test]
+    fn directory_patterns() {
+        assert!(protected("data/raw/", "data/raw", true));
+        assert!(protected("data/raw/", "data/raw/sample.csv", false));
+        assert!(!protected("data/raw/", "data/raw", false));
+        assert!(protected("cache/", "a/cache/x", false));
+    }
+
+    #[test]
+    fn double_star_patterns() {
+        assert!(protected("docs/**/*.pdf", "docs/a/b/c.pdf", false));
+        assert!(protected("docs/**/*.pdf", "docs/c.pdf", false));
+        assert!(protected("**/keys", "a/b/keys", true));
+        assert!(protected("vault/**", "vault/a/b", false));
+        assert!(!protected("docs/**/*.pdf", "docs/a/c.txt", false));
+    }
+
+    #[test]
+    fn negated_patterns() {
+        assert!(!protected("*.kdbx\n!scratch.kdbx", "scratch.kdbx", false));
+        assert!(protected("*.kdbx\n!scratch.kdbx", "main.kdbx", false));
+        // The last matching pattern wins.
+        assert!(protected("!scratch.kdbx\n*.kdbx", "scratch.kdbx", false));
+        // Files inside protected directories cannot be excluded.
+        assert!(protected("data/\n!data/tmp", "data/tmp", false));
+        // Escaped special characters
+        assert!(protected("\\!important", "!important", false));
+        assert!(protected("\\#notes", "#notes", false));
+        assert!(!protected("#notes", "#notes", false));
+    }
+
+    #[test]
+    fn parse_rule_file() {
+        use super::super::rulefiles::parse_rule_file;
+        use super::super::Action;
+
+        let dir = Path::new("/repo");
+        let rule_file = parse_rule_file(
+            dir,
+            "# Comment\n\n# action: confirm\n# reason: local database\n*.sqlite\n*.kdbx\n[invalid\n",
+        );
+        assert_eq!(rule_file.patterns.len(), 2);
+        let first = &rule_file.patterns[0].rule;
+        assert_eq!(first.action, Action::Confirm);
+        assert_eq!(first.reason.as_deref(), Some("local database"));
+        let second = &rule_file.patterns[1].rule;
+        assert_eq!(second.action, Action::Block);
+        assert_eq!(
+            second.reason.as_deref(),
+            Some("matches \"*.kdbx\" in /repo/.safe-rm-rules")
+        );
+    }
+
+    #[test]
+    fn rule_file_rule() {
+        use super::super::rulefiles::{rule_file_rule, RULE_FILE};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let project = root.join("project");
+        let nested = project.join("nested");
+        fs::create_dir_all(nested.join("keys")).unwrap();
+        File::create(nested.join("keys/id.kdbx")).unwrap();
+        File::create(nested.join("keys/scratch.kdbx")).unwrap();
+        File::create(nested.join("notes.txt")).unwrap();
+        writeln!(File::create(project.join(RULE_FILE)).unwrap(), "*.kdbx").unwrap();
+
+        assert!(rule_file_rule(&nested.join("keys/id.kdbx")).is_some());
+        assert!(rule_file_rule(&nested.join("notes.txt")).is_none());
+
+        // Deeper rule files take precedence.
+        writeln!(
+            File::create(nested.join(RULE_FILE)).unwrap(),
+            "!scratch.kdbx"
+        )
+        .unwrap();
+        assert!(rule_file_rule(&nested.join("keys/scratch.kdbx")).is_none());
+        assert!(rule_file_rule(&nested.join("keys/id.kdbx")).is_some());
+
+        // Directories containing protected files are checked by
+        // contents_rule() instead.
+        assert!(rule_file_rule(&nested).is_none());
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Temporary protections which don't require editing any configuration file:
+// the paths and globs listed in SAFE_RM_PROTECT protect the current process
+// tree, while "safe-rm protect --session" records paths in the runtime
+// directory of the login session, which is emptied on logout.
+
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, DirBuilder};
+use std::io;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+
+use super::{parse_line, read_config, Rule};
+
+pub const PROTECT_VAR: &str = "SAFE_RM_PROTECT";
+pub const RUNTIME_DIR_VAR: &str = "XDG_RUNTIME_DIR";
+
+// Colon-separated paths and globs, like $PATH.
+pub fn env_rules(value: &OsStr) -> Vec<Rule> {
+    let source = Path::new(PROTECT_VAR);
+    value
+        .to_string_lossy()
+        .split(':')
+        .filter(|pattern| !pattern.is_empty())
+        .filter_map(|pattern| parse_line(source.display(), Ok(pattern.to_string())))
+        .flatten()
+        .map(|path| Rule {
+            reason: Some(format!("listed in {}", PROTECT_VAR)),
+            ..Rule::new(path)
+        })
+        .collect()
+}
+
+// Where session protections are kept, if there is a login session.
+pub fn session_file(runtime_dir: Option<OsString>) -> Option<PathBuf> {
+    let runtime_dir = PathBuf::from(runtime_dir?);
+    if !runtime_dir.is_absolute() {
+        return None;
+    }
+    Some(runtime_dir.join("safe-rm").join("session"))
+}
+
+// Under sudo, the invoking user's session protections apply too.
+pub fn session_files(runtime_dir: Option<OsString>, invoking_uid: Option<u32>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = session_file(runtime_dir).into_iter().collect();
+    if let Some(file) = invoking_uid
+        .and_then(|uid| session_file(Some(OsString::from(format!("/run/user/{}", uid)))))
+    {
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    files
+}
+
+pub fn session_rules(file: &Path) -> Vec<Rule> {
+    let mut rules = read_config(file).unwrap_or_default();
+    for rule in &mut rules {
+        rule.reason = Some("protected for this session".to_string());
+    }
+    rules
+}
+
+// The file lists one path per line, escaped so that it matches only itself.
+fn session_entry(path: &Path) -> io::Result<String> {
+    let path = path.canonicalize()?;
+    Ok(Pattern::escape(&path.to_string_lossy()))
+}
+
+fn read_entries(file: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(file) {
+        Ok(contents) => Ok(contents.lines().map(String::from).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_entries(file: &Path, entries: &[String]) -> io::Result<()> {
+    if let Some(dir) = file.parent() {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+    let mut contents = entries.join("\n");
+    contents.push('\n');
+    fs::write(file, contents)
+}
+
+pub fn protect(file: &Path, path: &Path) -> io::Result<()> {
+    let entry = session_entry(path)?;
+    let mut entries = read_entries(file)?;
+    if !entries.contains(&entry) {
+        entries.push(entry);
+        write_entries(file, &entries)?;
+    }
+    Ok(())
+}
+
+pub fn unprotect(file: &Path, path: &Path) -> io::Result<()> {
+    let entry = session_entry(path)?;
+    let mut entries = read_entries(file)?;
+    if !entries.contains(&entry) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "not protected for this session",
+        ));
+    }
+    entries.retain(|e| *e != entry);
+    write_entries(file, &entries)
+}
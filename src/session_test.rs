@@ -0,0 +1,113 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::fs::{self, File};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn env_rules() {
+        use super::super::session::env_rules;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("a.db")).unwrap();
+        File::create(root.join("b.db")).unwrap();
+        fs::create_dir(root.join("data")).unwrap();
+
+        let value = format!(
+            "{}::{}",
+            root.join("data").display(),
+            root.join("*.db").display()
+        );
+        let rules = env_rules(&OsString::from(value));
+        let paths: Vec<PathBuf> = rules.iter().map(|rule| rule.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![root.join("data"), root.join("a.db"), root.join("b.db")]
+        );
+        assert_eq!(
+            rules[0].reason.as_deref(),
+            Some("listed in SAFE_RM_PROTECT")
+        );
+
+        assert!(env_rules(&OsString::new()).is_empty());
+        assert!(env_rules(&OsString::from(root.join("missing"))).is_empty());
+    }
+
+    #[test]
+    fn session_files() {
+        use super::super::session::{session_file, session_files};
+
+        assert_eq!(
+            session_file(Some(OsString::from("/run/user/1000"))),
+            Some(PathBuf::from("/run/user/1000/safe-rm/session"))
+        );
+        assert_eq!(session_file(Some(OsString::from("relative"))), None);
+        assert_eq!(session_file(Some(OsString::new())), None);
+        assert_eq!(session_file(None), None);
+
+        assert_eq!(
+            session_files(Some(OsString::from("/run/user/0")), Some(1000)),
+            vec![
+                PathBuf::from("/run/user/0/safe-rm/session"),
+                PathBuf::from("/run/user/1000/safe-rm/session")
+            ]
+        );
+        assert_eq!(
+            session_files(Some(OsString::from("/run/user/1000")), Some(1000)),
+            vec![PathBuf::from("/run/user/1000/safe-rm/session")]
+        );
+        assert!(session_files(None, None).is_empty());
+    }
+
+    #[test]
+    fn protect_and_unprotect() {
+        use super::super::matching_rule;
+        use super::super::session::{protect, session_rules, unprotect};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let file = root.join("runtime/safe-rm/session");
+        let protected = root.join("release [1]");
+        fs::create_dir(&protected).unwrap();
+        fs::create_dir(root.join("release 1")).unwrap();
+
+        assert!(session_rules(&file).is_empty());
+        protect(&file, &protected).unwrap();
+        protect(&file, &protected).unwrap();
+        let mode = fs::metadata(file.parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        // Glob characters in the path are taken literally.
+        let rules = session_rules(&file);
+        assert_eq!(rules.len(), 1);
+        let rule = matching_rule(&protected, &rules).unwrap();
+        assert_eq!(rule.reason.as_deref(), Some("protected for this session"));
+        assert!(matching_rule(&root.join("release 1"), &rules).is_none());
+
+        assert!(protect(&file, &root.join("missing")).is_err());
+        unprotect(&file, &protected).unwrap();
+        assert!(session_rules(&file).is_empty());
+        assert!(unprotect(&file, &protected).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Time-limited permission to delete one protected path, without bypassing
+// safe-rm and its audit log:
+//   safe-rm unlock /srv/old-release --for 10m --reason "decommissioned" --once
+//
+// Tokens only work for the user who created them, which is enforced by the
+// owner of the token file, or for the user root created them for with
+// --user. Rules from the system configuration or generated by safe-rm itself
+// can only be unlocked by root, and enforced rules can't be unlocked at all,
+// even when they protect something inside the directory being unlocked.
+
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{identity, Rule};
+
+// Root's tokens must survive sudo, which resets $XDG_RUNTIME_DIR.
+pub const ROOT_UNLOCK_DIR: &str = "/run/safe-rm/unlock";
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Token {
+    pub path: PathBuf,
+    pub uid: u32,
+    // Seconds since the epoch.
+    pub expires: u64,
+    pub reason: String,
+    // Whether the token is consumed by the first deletion.
+    pub once: bool,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Where the tokens of the given user are kept.
+pub fn unlock_dir(uid: u32, runtime_dir: Option<OsString>) -> Option<PathBuf> {
+    if uid == 0 {
+        return Some(PathBuf::from(ROOT_UNLOCK_DIR));
+    }
+    let runtime_dir = PathBuf::from(runtime_dir?);
+    if !runtime_dir.is_absolute() {
+        return None;
+    }
+    Some(runtime_dir.join("safe-rm").join("unlock"))
+}
+
+// One file per user and path, so that tokens can be found without listing
+// them all.
+fn token_file(dir: &Path, uid: u32, path: &Path) -> PathBuf {
+    let key = format!("{}:{}", uid, path.as_os_str().to_string_lossy());
+    let digest = Sha256::digest(key.as_bytes());
+    let name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    dir.join(name)
+}
+
+// Tokens created for somebody else must be readable by them. Since the
+// directory can't be listed, they still need to know the path to find it.
+pub fn create(dir: &Path, token: &Token) -> io::Result<()> {
+    DirBuilder::new().recursive(true).mode(0o711).create(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o711))?;
+    let contents =
+        toml::to_string(token).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let file = token_file(dir, token.uid, &token.path);
+    // A leftover token may belong to somebody else.
+    match fs::remove_file(&file) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => (),
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(if token.uid == identity::effective_uid() {
+            0o600
+        } else {
+            0o644
+        })
+        .open(file)?
+        .write_all(contents.as_bytes())
+}
+
+// The tokens available to a run of safe-rm.
+#[derive(Debug, Default)]
+pub struct Unlocks {
+    dir: Option<PathBuf>,
+    // Where root's tokens are, including those created for other users.
+    root_dir: Option<PathBuf>,
+    uid: u32,
+}
+
+impl Unlocks {
+    pub fn new(dir: Option<PathBuf>, root_dir: Option<PathBuf>, uid: u32) -> Unlocks {
+        Unlocks { dir, root_dir, uid }
+    }
+
+    // Returns the token allowing the deletion of a path protected by the
+    // given rule, if there is one, consuming it if it's single-use.
+    pub fn redeem(&self, path: &Path, rule: &Rule, now: u64) -> Option<Token> {
+        if rule.enforced {
+            return None;
+        }
+        let from_root = self
+            .root_dir
+            .as_deref()
+            .and_then(|dir| self.redeem_from(dir, 0, path, now));
+        if from_root.is_some() || (rule.system && self.uid != 0) {
+            return from_root;
+        }
+        self.dir
+            .as_deref()
+            .and_then(|dir| self.redeem_from(dir, self.uid, path, now))
+    }
+
+    // Tokens are only trusted if their file belongs to the given user.
+    fn redeem_from(&self, dir: &Path, owner: u32, path: &Path, now: u64) -> Option<Token> {
+        let file = token_file(dir, self.uid, path);
+        let metadata = fs::symlink_metadata(&file).ok()?;
+        if !metadata.is_file() || metadata.uid() != owner {
+            return None;
+        }
+        let token: Token = toml::from_str(&fs::read_to_string(&file).ok()?).ok()?;
+        if token.uid != self.uid || token.path != path {
+            return None;
+        }
+        if token.expires <= now || token.once {
+            // Without removing it, a single-use token could be used again.
+            if fs::remove_file(&file).is_err() || token.expires <= now {
+                return None;
+            }
+        }
+        Some(token)
+    }
+}
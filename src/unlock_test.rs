@@ -0,0 +1,185 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    use super::super::identity::effective_uid;
+    use super::super::unlock::{self, Token, Unlocks};
+    use super::super::Rule;
+
+    fn token(path: &str, expires: u64, once: bool) -> Token {
+        Token {
+            path: PathBuf::from(path),
+            uid: effective_uid(),
+            expires,
+            reason: "cleanup".to_string(),
+            once,
+        }
+    }
+
+    #[test]
+    fn unlock_dir() {
+        use super::super::unlock::unlock_dir;
+
+        assert_eq!(
+            unlock_dir(0, Some(OsString::from("/run/user/0"))),
+            Some(PathBuf::from("/run/safe-rm/unlock"))
+        );
+        assert_eq!(
+            unlock_dir(1000, Some(OsString::from("/run/user/1000"))),
+            Some(PathBuf::from("/run/user/1000/safe-rm/unlock"))
+        );
+        assert_eq!(unlock_dir(1000, Some(OsString::from("relative"))), None);
+        assert_eq!(unlock_dir(1000, None), None);
+    }
+
+    #[test]
+    fn redeem() {
+        let dir = tempdir().unwrap();
+        let unlock_dir = dir.path().join("unlock");
+        let now = 1_000_000;
+        let uid = effective_uid();
+        let unlocks = Unlocks::new(Some(unlock_dir.clone()), None, uid);
+        let rule = |path: &str| Rule::new(PathBuf::from(path));
+
+        unlock::create(&unlock_dir, &token("/reusable", now + 60, false)).unwrap();
+        unlock::create(&unlock_dir, &token("/once", now + 60, true)).unwrap();
+        unlock::create(&unlock_dir, &token("/expired", now, false)).unwrap();
+
+        // Reusable tokens last until they expire.
+        for _ in 0..2 {
+            assert_eq!(
+                unlocks.redeem(Path::new("/reusable"), &rule("/reusable"), now),
+                Some(token("/reusable", now + 60, false))
+            );
+        }
+        assert_eq!(
+            unlocks.redeem(Path::new("/reusable"), &rule("/reusable"), now + 60),
+            None
+        );
+        assert!(unlocks
+            .redeem(Path::new("/once"), &rule("/once"), now)
+            .is_some());
+        assert!(unlocks
+            .redeem(Path::new("/once"), &rule("/once"), now)
+            .is_none());
+        assert!(unlocks
+            .redeem(Path::new("/expired"), &rule("/expired"), now)
+            .is_none());
+        // Used up and expired tokens are removed.
+        assert_eq!(fs::read_dir(&unlock_dir).unwrap().count(), 0);
+
+        // Tokens only work for their owner.
+        unlock::create(&unlock_dir, &token("/other", now + 60, false)).unwrap();
+        let other_user = Unlocks::new(Some(unlock_dir.clone()), None, uid + 1);
+        assert!(other_user
+            .redeem(Path::new("/other"), &rule("/other"), now)
+            .is_none());
+        assert!(Unlocks::default()
+            .redeem(Path::new("/other"), &rule("/other"), now)
+            .is_none());
+
+        // Rules from the system configuration can only be unlocked by root.
+        let system_rule = Rule {
+            system: true,
+            ..rule("/other")
+        };
+        assert_eq!(
+            unlocks
+                .redeem(Path::new("/other"), &system_rule, now)
+                .is_some(),
+            uid == 0
+        );
+
+        // Enforced rules can't be unlocked.
+        let enforced_rule = Rule {
+            enforced: true,
+            ..system_rule
+        };
+        assert!(unlocks
+            .redeem(Path::new("/other"), &enforced_rule, now)
+            .is_none());
+    }
+
+    #[test]
+    fn redeem_from_root() {
+        use std::os::unix::fs::{chown, PermissionsExt};
+
+        // Tokens for other users can only be created by root.
+        if effective_uid() != 0 {
+            return;
+        }
+        let dir = tempdir().unwrap();
+        let root_dir = dir.path().join("root");
+        let user_dir = dir.path().join("user");
+        let now = 1_000_000;
+        let user = 4242;
+        let for_user = |path: &str| Token {
+            uid: user,
+            ..token(path, now + 60, false)
+        };
+        let system_rule = |path: &str| Rule {
+            system: true,
+            ..Rule::new(PathBuf::from(path))
+        };
+
+        unlock::create(&root_dir, &for_user("/srv")).unwrap();
+        unlock::create(&root_dir, &token("/var", now + 60, false)).unwrap();
+        // Created by the user
+        unlock::create(&user_dir, &for_user("/opt")).unwrap();
+        for entry in fs::read_dir(&user_dir).unwrap() {
+            chown(entry.unwrap().path(), Some(user), None).unwrap();
+        }
+
+        let unlocks = Unlocks::new(Some(user_dir.clone()), Some(root_dir.clone()), user);
+        assert_eq!(
+            unlocks.redeem(Path::new("/srv"), &system_rule("/srv"), now),
+            Some(for_user("/srv"))
+        );
+        assert!(unlocks
+            .redeem(Path::new("/srv"), &Rule::new(PathBuf::from("/srv")), now)
+            .is_some());
+        // Root's own tokens and those of other users don't work.
+        assert!(unlocks
+            .redeem(Path::new("/var"), &system_rule("/var"), now)
+            .is_none());
+        let other_user = Unlocks::new(None, Some(root_dir.clone()), user + 1);
+        assert!(other_user
+            .redeem(Path::new("/srv"), &system_rule("/srv"), now)
+            .is_none());
+        // Users can't unlock system rules by themselves.
+        assert!(unlocks
+            .redeem(Path::new("/opt"), &Rule::new(PathBuf::from("/opt")), now)
+            .is_some());
+        assert!(unlocks
+            .redeem(Path::new("/opt"), &system_rule("/opt"), now)
+            .is_none());
+
+        // The user can read the token without listing the directory.
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&root_dir), 0o711);
+        let modes: Vec<u32> = fs::read_dir(&root_dir)
+            .unwrap()
+            .map(|entry| mode(&entry.unwrap().path()))
+            .collect();
+        assert_eq!(modes.len(), 2);
+        assert!(modes.contains(&0o644) && modes.contains(&0o600));
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Files and directories can be protected with an extended attribute, which
+// unlike "chattr +i" doesn't require root and doesn't prevent writes:
+//   safe-rm protect --xattr PATH
+// The value of the attribute, if any, is used as the reason.
+
+use std::io;
+use std::path::Path;
+
+use super::Rule;
+
+pub const XATTR: &str = "user.safe-rm.protect";
+
+pub fn xattr_rule(path: &Path) -> Option<Rule> {
+    // Symlinks themselves cannot carry user attributes.
+    let value = xattr::get(path, XATTR).ok()??;
+    let reason = String::from_utf8_lossy(&value).trim().to_string();
+    Some(Rule {
+        reason: Some(if reason.is_empty() {
+            format!("marked with the {} attribute", XATTR)
+        } else {
+            reason
+        }),
+        ..Rule::new(path.to_path_buf())
+    })
+}
+
+pub fn protect(path: &Path, reason: &str) -> io::Result<()> {
+    xattr::set(path, XATTR, reason.as_bytes())
+}
+
+pub fn unprotect(path: &Path) -> io::Result<()> {
+    match xattr::get(path, XATTR)? {
+        Some(_) => xattr::remove(path, XATTR),
+        None => Ok(()),
+    }
+}
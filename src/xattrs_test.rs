@@ -0,0 +1,53 @@
+// Copyright (C) 2026 Francois Marier
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn xattr_rule() {
+        use super::super::xattrs::{protect, unprotect, xattr_rule, XATTR};
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("file");
+        File::create(&file_path).unwrap();
+        if xattr::set(&file_path, "user.safe-rm.test", b"").is_err() {
+            return; // The filesystem doesn't support extended attributes.
+        }
+
+        assert!(xattr_rule(&file_path).is_none());
+
+        protect(&file_path, "").unwrap();
+        assert_eq!(
+            xattr_rule(&file_path).unwrap().reason.unwrap(),
+            format!("marked with the {} attribute", XATTR)
+        );
+        protect(&file_path, "signed contracts").unwrap();
+        assert_eq!(
+            xattr_rule(&file_path).unwrap().reason.unwrap(),
+            "signed contracts"
+        );
+
+        unprotect(&file_path).unwrap();
+        assert!(xattr_rule(&file_path).is_none());
+        // Removing a missing attribute isn't an error.
+        unprotect(&file_path).unwrap();
+
+        assert!(xattr_rule(&dir.path().join("missing")).is_none());
+        assert!(unprotect(&dir.path().join("missing")).is_err());
+    }
+}